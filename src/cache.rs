@@ -0,0 +1,99 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::constants;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    /// How long cached listings are used without asking the server
+    pub ttl: Duration,
+    /// Use only cached listings, never the network
+    pub offline: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            ttl: constants::DEFAULT_CACHE_TTL,
+            offline: false,
+        }
+    }
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Listing {
+    pub url: String,
+    /// Seconds since the Unix epoch when the listing was last fetched or revalidated
+    pub fetched: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+impl Listing {
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_time().saturating_sub(self.fetched))
+    }
+}
+
+pub fn configure(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+pub fn settings() -> Settings {
+    SETTINGS.get().copied().unwrap_or_default()
+}
+
+pub fn is_offline() -> bool {
+    settings().offline
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Directory for data shared between runs, such as listings and search indexes
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(constants::CACHE_DIR))
+}
+
+// listings are stored by the hash of their URL, which is not a valid file name
+fn listing_path(url: &str) -> Option<PathBuf> {
+    let hash = format!("{:x}", Sha1::digest(url.as_bytes()));
+
+    cache_dir().map(|dir| {
+        dir.join(constants::LISTINGS_DIR)
+            .join(format!("{}.json", hash))
+    })
+}
+
+pub fn load_listing(url: &str) -> Option<Listing> {
+    let text = fs::read_to_string(listing_path(url)?).ok()?;
+    let listing: Listing = serde_json::from_str(&text).ok()?;
+
+    // guard against hash collisions
+    (listing.url == url).then_some(listing)
+}
+
+pub fn save_listing(listing: &Listing) -> io::Result<()> {
+    let path = listing_path(&listing.url)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cache directory available"))?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, serde_json::to_string(listing)?)
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::myrient::Rom;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    /// Add a number to the name, e.g. "Game (2).zip"
+    Suffix,
+    /// Put the file in a numbered subfolder, e.g. "Collision 2/Game.zip"
+    Subfolder,
+    /// Download only the first of the files
+    Skip,
+}
+
+// A file whose local path is already used by another file in the set
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    pub rom: Rom,
+    /// Path shared with the other file
+    pub path: String,
+    /// Path used instead, none if the file is skipped
+    pub resolved: Option<String>,
+}
+
+// paths that are the same file on case-insensitive or normalizing filesystems
+fn key(path: &str) -> String {
+    path.nfd().collect::<String>().to_lowercase()
+}
+
+fn split_path(path: &str) -> (&str, &str) {
+    match path.rfind('/') {
+        Some(slash) => (&path[..slash + 1], &path[slash + 1..]),
+        None => ("", path),
+    }
+}
+
+fn with_suffix(path: &str, number: usize) -> String {
+    let (dir, file) = split_path(path);
+
+    match file.rfind('.') {
+        Some(dot) if dot > 0 => format!("{}{} ({}){}", dir, &file[..dot], number, &file[dot..]),
+        _ => format!("{}{} ({})", dir, file, number),
+    }
+}
+
+fn in_subfolder(path: &str, number: usize) -> String {
+    let (dir, file) = split_path(path);
+
+    format!("{}Collision {}/{}", dir, number, file)
+}
+
+/// Finds files in the set that would end up as the same local file on a
+/// case-insensitive or Unicode-normalizing filesystem, and resolves them with
+/// the strategy. The first file keeps its path.
+pub fn resolve(roms: Vec<Rom>, strategy: Strategy) -> (Vec<Rom>, Vec<Resolution>) {
+    let mut taken: HashSet<String> = roms.iter().map(|rom| key(rom.local_path())).collect();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut kept: Vec<Rom> = Vec::new();
+    let mut resolutions: Vec<Resolution> = Vec::new();
+
+    for mut rom in roms {
+        let path = rom.local_path().to_string();
+
+        if seen.insert(key(&path)) {
+            kept.push(rom);
+            continue;
+        }
+
+        let resolved = match strategy {
+            Strategy::Skip => None,
+            Strategy::Suffix | Strategy::Subfolder => (2..)
+                .map(|number| match strategy {
+                    Strategy::Subfolder => in_subfolder(&path, number),
+                    _ => with_suffix(&path, number),
+                })
+                .find(|candidate| !taken.contains(&key(candidate))),
+        };
+
+        if let Some(resolved) = &resolved {
+            taken.insert(key(resolved));
+            seen.insert(key(resolved));
+            rom.path = Some(resolved.clone());
+            kept.push(rom.clone());
+        }
+
+        resolutions.push(Resolution {
+            rom,
+            path,
+            resolved,
+        });
+    }
+
+    (kept, resolutions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom(path: &str) -> Rom {
+        Rom {
+            name: path.to_string(),
+            file: path.to_string(),
+            url: path.to_string(),
+            size: None,
+            path: None,
+        }
+    }
+
+    fn paths(roms: &[Rom]) -> Vec<&str> {
+        roms.iter().map(Rom::local_path).collect()
+    }
+
+    #[test]
+    fn keeps_paths_without_collisions() {
+        let (kept, resolutions) = resolve(vec![rom("a.zip"), rom("b.zip")], Strategy::Suffix);
+
+        assert_eq!(paths(&kept), ["a.zip", "b.zip"]);
+        assert!(resolutions.is_empty());
+    }
+
+    #[test]
+    fn adds_suffixes_to_case_collisions() {
+        let roms = vec![
+            rom("dir/Game.zip"),
+            rom("dir/GAME.zip"),
+            rom("dir/game.zip"),
+            rom("dir/Game (2).zip"),
+        ];
+        let (kept, resolutions) = resolve(roms, Strategy::Suffix);
+
+        assert_eq!(
+            paths(&kept),
+            [
+                "dir/Game.zip",
+                "dir/GAME (3).zip",
+                "dir/game (4).zip",
+                "dir/Game (2).zip"
+            ]
+        );
+        assert_eq!(resolutions.len(), 2);
+        assert_eq!(resolutions[0].path, "dir/GAME.zip");
+    }
+
+    #[test]
+    fn finds_unicode_normalization_collisions() {
+        let composed = "Pok\u{e9}mon.zip";
+        let decomposed = "Poke\u{301}mon.zip";
+        let (kept, _) = resolve(vec![rom(composed), rom(decomposed)], Strategy::Subfolder);
+
+        assert_eq!(
+            paths(&kept),
+            [composed.to_string(), format!("Collision 2/{}", decomposed)]
+        );
+    }
+
+    #[test]
+    fn skips_collisions() {
+        let (kept, resolutions) = resolve(vec![rom("a.zip"), rom("A.zip")], Strategy::Skip);
+
+        assert_eq!(paths(&kept), ["a.zip"]);
+        assert_eq!(resolutions[0].resolved, None);
+    }
+}
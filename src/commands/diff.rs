@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use colored::Colorize;
+
+use super::load_dat;
+use crate::dat;
+use crate::diff;
+use crate::output;
+use crate::say;
+use crate::DiffArgs;
+
+// version and date from the header of a DAT-file
+pub fn read_dat_version(input: &str) -> (Option<String>, Option<String>) {
+    match std::fs::read_to_string(input) {
+        Ok(text) => dat::parse(&text).map_or((None, None), |dat| dat::get_version(&dat)),
+        Err(_) => (None, None),
+    }
+}
+
+pub fn describe_version(version: &Option<String>, date: &Option<String>) -> String {
+    match (version, date) {
+        (Some(version), _) => format!("version {}", version),
+        (None, Some(date)) => format!("dated {}", date),
+        (None, None) => "unknown version".to_string(),
+    }
+}
+
+pub fn print_changes(changes: &[diff::Change]) {
+    for change in changes {
+        let line = match (change.kind, &change.old) {
+            (diff::Kind::Renamed, Some(old)) => {
+                format!("{:10} {} -> {}", change.kind, old.name, change.name())
+            }
+            _ => format!("{:10} {}", change.kind, change.name()),
+        };
+
+        match change.kind {
+            diff::Kind::Added => say!("{}", line.green()),
+            diff::Kind::Removed => say!("{}", line.red()),
+            diff::Kind::Renamed => say!("{}", line.cyan()),
+            diff::Kind::Rehashed => say!("{}", line.yellow()),
+        }
+
+        output::emit(output::Event::Changed {
+            change: change.kind.to_string().to_lowercase(),
+            name: change.name().to_string(),
+            old_name: match change.kind {
+                diff::Kind::Renamed => change.old.as_ref().map(|old| old.name.clone()),
+                _ => None,
+            },
+        });
+    }
+
+    for (kind, label) in [
+        (diff::Kind::Added, "added"),
+        (diff::Kind::Removed, "removed"),
+        (diff::Kind::Renamed, "renamed"),
+        (diff::Kind::Rehashed, "rehashed"),
+    ] {
+        let label = format!("Amount of {} games", label);
+        say!(
+            "{}",
+            format!("{:<36}: {}", label, diff::count(changes, kind)).green()
+        );
+    }
+}
+
+pub fn run(args: DiffArgs) {
+    for input in [&args.old, &args.new] {
+        if !Path::new(input).is_file() {
+            say!("{}", format!("Invalid DAT-file {}!", input).red());
+            output::exit(1);
+        }
+    }
+
+    let (_, _, old_games) = load_dat(&args.old);
+    let (_, _, new_games) = load_dat(&args.new);
+
+    let (old_version, old_date) = read_dat_version(&args.old);
+    let (new_version, new_date) = read_dat_version(&args.new);
+    say!(
+        "{}",
+        format!(
+            "Comparing {} to {}",
+            describe_version(&old_version, &old_date),
+            describe_version(&new_version, &new_date)
+        )
+        .green()
+    );
+
+    print_changes(&diff::compare(&old_games, &new_games));
+}
@@ -0,0 +1,1255 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use colored::Colorize;
+
+use super::diff::{describe_version, print_changes, read_dat_version};
+use super::{confirm_or_exit, fetch_or_exit, get_catalog_url, get_collection_url, load_dat};
+use crate::cache;
+use crate::collision;
+use crate::constants;
+use crate::dat;
+use crate::diff;
+use crate::discs;
+use crate::export;
+use crate::filter;
+use crate::interrupt;
+use crate::layout;
+use crate::manifest;
+use crate::myrient;
+use crate::one_game_one_rom;
+use crate::output;
+use crate::plan;
+use crate::prune;
+use crate::rebuild;
+use crate::report;
+use crate::retry;
+use crate::sanitize;
+use crate::say;
+use crate::state;
+use crate::tags;
+use crate::throttle;
+use crate::tui;
+use crate::verify;
+use crate::wanted_list;
+use crate::{DownloadArgs, TransferArgs};
+
+pub fn run(args: DownloadArgs) {
+    validate_args(&args);
+
+    let mut output_dir = args.output.clone().unwrap_or_default();
+    if (cfg!(windows) && output_dir.ends_with('\\')) || (cfg!(unix) && output_dir.ends_with('/')) {
+        output_dir = output_dir[..output_dir.len() - 1].to_string();
+    }
+
+    say!("{}", format!("Output directory: {}", output_dir).green());
+
+    let retry_policy = build_retry_policy(&args.transfer);
+    let throttle = build_throttle(&args.transfer);
+
+    if args.retry_failed {
+        retry_failed(
+            &output_dir,
+            &retry_policy,
+            &throttle,
+            args.transfer.ignore_free_space,
+        );
+        return;
+    }
+
+    let mut system = String::new();
+    let mut catalog_name = String::new();
+    let mut all_games: Vec<dat::Game> = Vec::new();
+    let mut wanted_games: Vec<dat::Game> = Vec::new();
+    let mut wanted_rom_names: Vec<String> = Vec::new();
+    let mut wanted_titles: Vec<String> = Vec::new();
+
+    match &args.input {
+        Some(input) if wanted_list::is_wanted_list(input) => {
+            say!("{}", "Opening input wanted-list...".green());
+            wanted_titles = wanted_list::read(input).unwrap_or_else(|_| {
+                say!("{}", "Error opening wanted-list!".red());
+                output::exit(1);
+            });
+        }
+        Some(input) => {
+            (system, catalog_name, all_games) = load_dat(input);
+            wanted_games = select_games(&all_games, &args);
+            if args.update {
+                select_updated_games(&args, input, &output_dir, &all_games, &mut wanted_games);
+            }
+            wanted_rom_names = dat::get_wanted_roms(&wanted_games);
+        }
+        None => {}
+    }
+
+    if let Some(collection) = &args.collection {
+        system = collection.clone();
+    } else if let Some(collection) = args.collection_map.get(&system) {
+        system = collection.clone();
+    }
+    if let Some(name) = &args.catalog_name {
+        catalog_name = name.clone();
+    }
+
+    let mut browsed_roms: Option<Vec<myrient::Rom>> = None;
+
+    let (catalog_url, collection_url) = if args.browse {
+        let selection = tui::browse(&wanted_rom_names, &catalog_name, &system)
+            .unwrap_or_else(|e| {
+                say!("{}", format!("Error in browser: {}", e).red());
+                output::exit(1);
+            })
+            .unwrap_or_else(|| {
+                say!("{}", "Cancelled.".yellow());
+                output::exit(0);
+            });
+
+        browsed_roms = Some(selection.roms);
+        (selection.catalog_url, selection.collection_url)
+    } else {
+        let catalog_url = get_catalog_url(&catalog_name, &args.catalog);
+        let collection_url = get_collection_url(&catalog_url, &system, &args.system);
+        (catalog_url, collection_url)
+    };
+
+    output::emit(output::Event::Resolved {
+        catalog_url: catalog_url.clone(),
+        collection_url: collection_url.clone(),
+    });
+
+    let collection_html = fetch_or_exit(&format!("{}{}", &catalog_url, &collection_url));
+
+    let available_roms = myrient::get_roms_for_collection(&collection_html);
+
+    let mut missing_roms: Vec<String> = Vec::new();
+    let mut wanted_roms: Vec<myrient::Rom> = Vec::new();
+
+    for wanted_rom_name in wanted_rom_names.iter() {
+        if available_roms.contains_key(wanted_rom_name) {
+            wanted_roms.push(available_roms[wanted_rom_name].clone());
+        } else {
+            missing_roms.push(wanted_rom_name.to_string());
+        }
+    }
+
+    // files picked in the browser replace the matched ones
+    if let Some(roms) = browsed_roms {
+        wanted_roms = roms;
+    }
+
+    if !wanted_titles.is_empty() {
+        let matches =
+            wanted_list::match_titles(&wanted_titles, &available_roms, &args.region_priority);
+
+        for title_match in matches {
+            match title_match.rom {
+                Some(rom) => {
+                    say!(
+                        "{}",
+                        format!(
+                            "{} -> {} ({:.0}%)",
+                            title_match.query,
+                            rom.name,
+                            title_match.score * 100.0
+                        )
+                        .cyan()
+                    );
+                    if !wanted_roms.iter().any(|wanted| wanted.name == rom.name) {
+                        wanted_roms.push(rom);
+                    }
+                }
+                None => {
+                    say!("{}", format!("{} -> no match", title_match.query).yellow());
+                    missing_roms.push(title_match.query);
+                }
+            }
+        }
+
+        if !args.yes && !args.dry_run && !wanted_roms.is_empty() {
+            confirm_or_exit("Download the matched ROMs?");
+        }
+    }
+
+    if let Some(template) = &args.layout {
+        layout::apply(
+            &mut wanted_roms,
+            template,
+            &system,
+            &catalog_name,
+            &catalog_url,
+            &collection_url,
+            &all_games,
+        );
+    }
+    if args.disc_folders {
+        let count = discs::apply_folders(&mut wanted_roms);
+        if count > 0 {
+            say!(
+                "{}",
+                format!("Multi-disc games in their own folders: {}", count).green()
+            );
+        }
+    }
+    prepare_paths(&mut wanted_roms, &args.transfer, &output_dir, !args.dry_run);
+    if args.rebuild {
+        rebuild_files(&output_dir, &system, &wanted_roms, &all_games, args.dry_run);
+    }
+
+    let missing_roms_len = missing_roms.len();
+
+    say!(
+        "{}",
+        format!(
+            "Amount of wanted ROMs in DAT-file   : {}",
+            wanted_roms.len()
+        )
+        .green()
+    );
+    say!(
+        "{}",
+        format!(
+            "Amount of found ROMs at server      : {}",
+            available_roms.len()
+        )
+        .green()
+    );
+    if missing_roms_len > 0 {
+        say!(
+            "{}",
+            format!("Amount of missing ROMs at server    : {}", missing_roms_len).yellow()
+        );
+    }
+
+    output::emit(output::Event::Counts {
+        wanted: wanted_roms.len() + missing_roms_len,
+        found: wanted_roms.len(),
+        missing: missing_roms_len,
+    });
+    for missing_rom in missing_roms.iter() {
+        output::emit(output::Event::Rom {
+            name: missing_rom.clone(),
+            status: output::RomStatus::Missing,
+            size: None,
+            detail: None,
+        });
+    }
+
+    let url_prefix = format!("{}{}", catalog_url, collection_url);
+
+    if args.dry_run {
+        let dat_sizes = dat::get_rom_sizes(&all_games);
+        // exact sizes need a request per file
+        let exact = !cache::is_offline();
+        let plan = plan::build(&wanted_roms, &output_dir, &url_prefix, &dat_sizes, exact);
+
+        plan::print_entries(&plan);
+        for entry in plan.entries.iter() {
+            output::emit(output::Event::Rom {
+                name: entry.rom.name.clone(),
+                status: output::RomStatus::Planned,
+                size: entry.remote_size,
+                detail: Some(entry.status.to_string().to_lowercase()),
+            });
+        }
+        plan::print_summary(&plan);
+        if let Err(e) = plan::check_free_space(&plan, &output_dir) {
+            say!("{}", e.red());
+        }
+    } else {
+        let dat_sizes = dat::get_rom_sizes(&all_games);
+        let plan = plan::build(&wanted_roms, &output_dir, &url_prefix, &dat_sizes, false);
+
+        plan::print_summary(&plan);
+        ensure_free_space(&plan, &output_dir, args.transfer.ignore_free_space);
+
+        let result = myrient::download_roms(
+            &wanted_roms,
+            &output_dir,
+            &catalog_url,
+            &collection_url,
+            &retry_policy,
+            &throttle,
+        );
+        handle_download_result(&result, &output_dir, &catalog_url, &collection_url);
+
+        if let (Ok(_), Some(input)) = (&result, &args.input) {
+            if missing_roms.is_empty() && !args.browse && !wanted_list::is_wanted_list(input) {
+                save_synced(input, &output_dir, &system);
+            }
+        }
+
+        write_run_results(
+            &args,
+            &output_dir,
+            &system,
+            &wanted_games,
+            &missing_roms,
+            &result,
+        );
+        exit_if_interrupted(&result);
+    }
+
+    if missing_roms_len > 0 {
+        say!(
+            "{}",
+            format!(
+                "Following {} ROMs in DAT not automatically found from server, grab these manually:",
+                missing_roms_len
+            )
+            .red()
+        );
+
+        for missing_rom in missing_roms.iter() {
+            say!("{}", missing_rom.yellow());
+        }
+    } else {
+        say!("{}", "All wanted ROMs found from server!".green());
+    }
+}
+
+// makes the local paths valid for the filesystem and resolves collisions
+// between them, saving renames for later runs
+pub fn prepare_paths(
+    roms: &mut Vec<myrient::Rom>,
+    transfer: &TransferArgs,
+    output_dir: &str,
+    save: bool,
+) {
+    let mut names = state::load_names(output_dir).unwrap_or_default();
+    let saved = names.clone();
+
+    let raw_paths: HashMap<String, String> = roms
+        .iter()
+        .map(|rom| (rom.url.clone(), rom.local_path().to_string()))
+        .collect();
+
+    sanitize::apply(roms, transfer.sanitize, output_dir, &mut names);
+
+    let (kept, resolutions) = collision::resolve(std::mem::take(roms), transfer.on_collision);
+    *roms = kept;
+
+    for resolution in resolutions {
+        match &resolution.resolved {
+            Some(resolved) => {
+                say!(
+                    "{}",
+                    format!(
+                        "Name collision at {}, saving {} as {}",
+                        resolution.path, resolution.rom.name, resolved
+                    )
+                    .yellow()
+                );
+                names.insert(raw_paths[&resolution.rom.url].clone(), resolved.clone());
+            }
+            None => say!(
+                "{}",
+                format!(
+                    "Name collision at {}, skipping {}",
+                    resolution.path, resolution.rom.name
+                )
+                .yellow()
+            ),
+        }
+
+        output::emit(output::Event::Collision {
+            name: resolution.rom.name,
+            path: resolution.path,
+            resolved: resolution.resolved,
+        });
+    }
+
+    if save && names != saved {
+        if let Err(e) = state::save_names(output_dir, &names) {
+            say!(
+                "{}",
+                format!("Error saving renamed file names: {}", e).red()
+            );
+        }
+    }
+}
+
+// renames local files with the contents of wanted ROMs before the download plan
+fn rebuild_files(
+    output_dir: &str,
+    system: &str,
+    roms: &[myrient::Rom],
+    games: &[dat::Game],
+    dry_run: bool,
+) {
+    let names = state::load_names(output_dir).unwrap_or_default();
+    let renames = match rebuild::find(Path::new(output_dir), system, roms, games, &names) {
+        Ok(renames) => renames,
+        Err(e) => {
+            say!("{}", format!("Error matching local files: {}", e).red());
+            return;
+        }
+    };
+
+    let mut count = 0;
+    for rename in renames.iter() {
+        let from = rename.from.display().to_string();
+        let to = rename.to.display().to_string();
+
+        if dry_run {
+            say!("{}", format!("Would rename {} to {}", from, to).cyan());
+        } else if let Err(e) = rebuild::rename(rename) {
+            say!(
+                "{}",
+                format!("Error renaming {} to {}: {}", from, to, e).red()
+            );
+            continue;
+        } else {
+            say!("{}", format!("Renamed {} to {}", from, to).cyan());
+        }
+
+        count += 1;
+        output::emit(output::Event::Rebuilt {
+            name: rename.rom.name.clone(),
+            from,
+            to,
+        });
+    }
+
+    if count > 0 {
+        say!(
+            "{}",
+            format!("Local files matched by hash         : {}", count).green()
+        );
+    }
+}
+
+pub fn build_retry_policy(args: &TransferArgs) -> retry::RetryPolicy {
+    retry::RetryPolicy {
+        retries: args.retries,
+        base_delay: Duration::from_millis(args.retry_delay),
+        max_delay: Duration::from_millis(args.retry_max_delay),
+        jitter: args.retry_jitter,
+    }
+}
+
+pub fn build_throttle(args: &TransferArgs) -> throttle::Throttle {
+    let throttle = throttle::Throttle::new(args.limit_rate.flatten(), args.schedule.clone());
+
+    if args.limit_rate.is_some() || !args.schedule.is_empty() {
+        say!(
+            "{}",
+            format!(
+                "Download speed limit: {}",
+                throttle::format_rate(throttle.current_rate())
+            )
+            .green()
+        );
+    }
+
+    throttle
+}
+
+// remembers the DAT-file the output was completely downloaded from, for --update
+fn save_synced(input: &str, output_dir: &str, system: &str) {
+    let result = std::fs::read_to_string(input).and_then(|text| {
+        let (version, date) = dat::parse(&text).map_or((None, None), |dat| dat::get_version(&dat));
+        let synced = state::Synced {
+            name: system.to_string(),
+            version,
+            date,
+        };
+        state::save_synced(output_dir, &synced, &text)
+    });
+
+    if let Err(e) = result {
+        say!("{}", format!("Error saving synced DAT-file: {}", e).red());
+    }
+}
+
+// keeps the wanted games that changed since the DAT-file the output was synced
+// to, moving their outdated files and with --prune-removed files of removed
+// games to quarantine
+fn select_updated_games(
+    args: &DownloadArgs,
+    input: &str,
+    output_dir: &str,
+    all_games: &[dat::Game],
+    wanted_games: &mut Vec<dat::Game>,
+) {
+    let (synced, old_text) = state::load_synced(output_dir).unwrap_or_else(|_| {
+        say!(
+            "{}",
+            "No synced DAT-file in the output path, download without --update once first!".red()
+        );
+        output::exit(1);
+    });
+    let old_games = match dat::parse(&old_text) {
+        Ok(dat) => dat::get_games(&dat),
+        Err(_) => {
+            say!("{}", "Error parsing the synced DAT-file!".red());
+            output::exit(1);
+        }
+    };
+
+    let (version, date) = read_dat_version(input);
+    say!(
+        "{}",
+        format!(
+            "Updating {} from {} to {}...",
+            synced.name,
+            describe_version(&synced.version, &synced.date),
+            describe_version(&version, &date)
+        )
+        .green()
+    );
+
+    let changes = diff::compare(&old_games, all_games);
+    print_changes(&changes);
+
+    let names = state::load_names(output_dir).unwrap_or_default();
+    let local_files = verify::find_local_files(Path::new(output_dir), &names).unwrap_or_default();
+    let new_roms_by_stem = dat::get_roms_by_stem(all_games);
+    let new_stems: HashSet<String> = dat::get_wanted_roms(all_games).into_iter().collect();
+
+    // files to move away before downloading, with the reason
+    let mut outdated: Vec<(PathBuf, String)> = Vec::new();
+
+    for change in changes.iter() {
+        match (change.kind, &change.old, &change.new) {
+            (diff::Kind::Rehashed, _, Some(game)) => {
+                for stem in dat::get_wanted_roms(std::slice::from_ref(game)) {
+                    if let Some((file_name, path)) = local_files.get(&stem) {
+                        let dat_roms = new_roms_by_stem.get(&stem).cloned().unwrap_or_default();
+                        if let verify::Verification::Failed(_) =
+                            verify::verify_file(path, file_name, &dat_roms)
+                        {
+                            outdated.push((path.clone(), "outdated".to_string()));
+                        }
+                    }
+                }
+            }
+            // renamed files are kept for --rebuild to rename
+            (diff::Kind::Removed | diff::Kind::Renamed, Some(game), _)
+                if args.prune_removed && !(args.rebuild && change.kind == diff::Kind::Renamed) =>
+            {
+                for stem in dat::get_wanted_roms(std::slice::from_ref(game)) {
+                    if let (false, Some((_, path))) =
+                        (new_stems.contains(&stem), local_files.get(&stem))
+                    {
+                        outdated.push((path.clone(), change.kind.to_string().to_lowercase()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let quarantine_dir = Path::new(output_dir)
+        .join(constants::STATE_DIR)
+        .join(constants::QUARANTINE_DIR);
+
+    for (path, reason) in outdated {
+        if args.dry_run {
+            say!(
+                "{}",
+                format!(
+                    "Would move {} file {} to quarantine",
+                    reason,
+                    path.display()
+                )
+                .yellow()
+            );
+            continue;
+        }
+
+        match prune::quarantine(Path::new(output_dir), &path, &quarantine_dir) {
+            Ok(target) => say!(
+                "{}",
+                format!(
+                    "Moved {} file {} to {}",
+                    reason,
+                    path.display(),
+                    target.display()
+                )
+                .yellow()
+            ),
+            Err(e) => say!(
+                "{}",
+                format!("Error moving {} to quarantine: {}", path.display(), e).red()
+            ),
+        }
+    }
+
+    let changed: HashSet<&str> = changes
+        .iter()
+        .filter_map(|change| change.new.as_ref())
+        .map(|game| game.name.as_str())
+        .collect();
+    wanted_games.retain(|game| changed.contains(game.name.as_str()));
+}
+
+fn select_games(all_games: &[dat::Game], args: &DownloadArgs) -> Vec<dat::Game> {
+    let read_patterns = |paths: &Vec<String>| {
+        let mut patterns: Vec<filter::Pattern> = Vec::new();
+        for path in paths {
+            match filter::read_patterns(path) {
+                Ok(file_patterns) => patterns.extend(file_patterns),
+                Err(e) => {
+                    say!("{}", e.red());
+                    output::exit(1);
+                }
+            }
+        }
+        patterns
+    };
+
+    let mut include_patterns = args.include.clone();
+    include_patterns.extend(read_patterns(&args.include_file));
+    let mut exclude_patterns = args.exclude.clone();
+    exclude_patterns.extend(read_patterns(&args.exclude_file));
+
+    let game_filter = filter::GameFilter {
+        include_regions: args.include_region.clone(),
+        exclude_regions: args.exclude_region.clone(),
+        include_languages: args.include_language.clone(),
+        exclude_languages: args.exclude_language.clone(),
+        include_tags: args.include_tag.clone(),
+        exclude_tags: args.exclude_tag.clone(),
+        include_patterns,
+        exclude_patterns,
+        include_categories: args.include_category.clone(),
+        exclude_categories: args.exclude_category.clone(),
+    };
+
+    let mut wanted_games = filter::apply(all_games.to_vec(), &game_filter);
+
+    if wanted_games.len() < all_games.len() {
+        say!(
+            "{}",
+            format!(
+                "Amount of games filtered out        : {}",
+                all_games.len() - wanted_games.len()
+            )
+            .green()
+        );
+    }
+
+    if args.one_game_one_rom {
+        let priorities = one_game_one_rom::Priorities {
+            regions: args.region_priority.clone(),
+            languages: args.language_priority.clone(),
+        };
+        let (games, selections) = one_game_one_rom::select(wanted_games, &priorities);
+        wanted_games = games;
+
+        for selection in selections.iter().filter(|s| s.candidates > 1) {
+            say!(
+                "{}",
+                format!(
+                    "{}: chose {} out of {} ({})",
+                    selection.parent, selection.chosen, selection.candidates, selection.reason
+                )
+                .cyan()
+            );
+        }
+
+        say!(
+            "{}",
+            format!(
+                "Amount of games after 1G1R          : {}",
+                wanted_games.len()
+            )
+            .green()
+        );
+    }
+
+    wanted_games
+}
+
+fn retry_failed(
+    output_dir: &str,
+    retry_policy: &retry::RetryPolicy,
+    throttle: &throttle::Throttle,
+    ignore_free_space: bool,
+) {
+    say!("{}", "Loading previously failed ROMs...".green());
+    let failed = match state::load_failed_roms(output_dir) {
+        Ok(failed) => failed,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            say!("{}", "No previously failed ROMs found!".yellow());
+            output::exit(0);
+        }
+        Err(e) => {
+            say!(
+                "{}",
+                format!("Error reading previously failed ROMs: {}", e).red()
+            );
+            output::exit(1);
+        }
+    };
+
+    say!(
+        "{}",
+        format!(
+            "Amount of ROMs to retry             : {}",
+            failed.roms.len()
+        )
+        .green()
+    );
+
+    let plan = plan::build(
+        &failed.roms,
+        output_dir,
+        &format!("{}{}", failed.catalog_url, failed.collection_url),
+        &HashMap::new(),
+        false,
+    );
+    plan::print_summary(&plan);
+    ensure_free_space(&plan, output_dir, ignore_free_space);
+
+    let result = myrient::retry_failed_roms(
+        &failed.roms,
+        output_dir,
+        &failed.catalog_url,
+        &failed.collection_url,
+        retry_policy,
+        throttle,
+    );
+    handle_download_result(
+        &result,
+        output_dir,
+        &failed.catalog_url,
+        &failed.collection_url,
+    );
+    exit_if_interrupted(&result);
+}
+
+pub fn ensure_free_space(plan: &plan::Plan, output_dir: &str, ignore_free_space: bool) {
+    if let Err(e) = plan::check_free_space(plan, output_dir) {
+        if ignore_free_space {
+            say!("{}", e.yellow());
+        } else {
+            say!("{}", e.red());
+            say!("{}", "Use --ignore-free-space to download anyway.".yellow());
+            output::exit(1);
+        }
+    }
+}
+
+pub fn handle_download_result(
+    result: &Result<myrient::DownloadSummary, myrient::BulkDownloadError>,
+    output_dir: &str,
+    catalog_url: &str,
+    collection_url: &str,
+) {
+    let finished = match result {
+        Ok(summary) => output::Event::Finished {
+            downloaded: summary.downloaded_roms.len(),
+            already_downloaded: summary.already_downloaded_roms.len(),
+            failed: 0,
+            remaining: 0,
+            interrupted: false,
+        },
+        Err(err) => output::Event::Finished {
+            downloaded: err.summary.downloaded_roms.len(),
+            already_downloaded: err.summary.already_downloaded_roms.len(),
+            failed: err.failed_roms.len(),
+            remaining: err.remaining_roms.len(),
+            interrupted: err.interrupted,
+        },
+    };
+    output::emit(finished);
+
+    match result {
+        Ok(_) => {
+            say!("{}", "All downloads successful!".green());
+
+            if let Err(e) = state::clear_failed_roms(output_dir) {
+                say!(
+                    "{}",
+                    format!("Error clearing failed ROMs list: {}", e).red()
+                );
+            }
+        }
+        Err(err) => {
+            if !err.failed_roms.is_empty() {
+                say!(
+                    "{}",
+                    format!(
+                        "Following {} ROMs failed to download:",
+                        err.failed_roms.len()
+                    )
+                    .red()
+                );
+
+                for rom in err.failed_roms.iter() {
+                    say!("{}", rom.name.red());
+                }
+            }
+
+            if err.interrupted {
+                say!("{}", "Download interrupted!".yellow());
+                say!(
+                    "{}",
+                    format!(
+                        "Amount of completed ROMs            : {}",
+                        err.summary.completed_count()
+                    )
+                    .green()
+                );
+                say!(
+                    "{}",
+                    format!(
+                        "Amount of remaining ROMs            : {}",
+                        err.remaining_roms.len()
+                    )
+                    .yellow()
+                );
+            }
+
+            // remaining ROMs are retried along with the failed ones
+            let mut roms = err.failed_roms.clone();
+            roms.extend(err.remaining_roms.iter().cloned());
+
+            let failed = state::FailedDownloads {
+                catalog_url: catalog_url.to_string(),
+                collection_url: collection_url.to_string(),
+                roms,
+            };
+
+            match state::save_failed_roms(output_dir, &failed) {
+                Ok(path) => say!(
+                    "{}",
+                    format!(
+                        "Unfinished ROMs saved to {}, run again with --retry-failed to continue",
+                        path.display()
+                    )
+                    .yellow()
+                ),
+                Err(e) => say!("{}", format!("Error saving failed ROMs list: {}", e).red()),
+            }
+        }
+    }
+}
+
+pub fn exit_if_interrupted(result: &Result<myrient::DownloadSummary, myrient::BulkDownloadError>) {
+    if let Err(err) = result {
+        if err.interrupted {
+            output::exit(interrupt::EXIT_CODE_INTERRUPTED);
+        }
+    }
+}
+
+// verifies the downloads if asked, then writes the have/miss DATs and reports
+fn write_run_results(
+    args: &DownloadArgs,
+    output_dir: &str,
+    system: &str,
+    wanted_games: &[dat::Game],
+    missing_roms: &[String],
+    result: &Result<myrient::DownloadSummary, myrient::BulkDownloadError>,
+) {
+    let (summary, failed_roms, remaining_roms) = match result {
+        Ok(summary) => (summary.clone(), Vec::new(), Vec::new()),
+        Err(err) => (
+            err.summary.clone(),
+            err.failed_roms.clone(),
+            err.remaining_roms.clone(),
+        ),
+    };
+
+    let roms_by_stem = dat::get_roms_by_stem(wanted_games);
+    let mut items: Vec<report::Item> = Vec::new();
+
+    let completed = summary
+        .downloaded_roms
+        .iter()
+        .map(|rom| (rom, report::Status::Downloaded))
+        .chain(
+            summary
+                .already_downloaded_roms
+                .iter()
+                .map(|rom| (rom, report::Status::AlreadyDownloaded)),
+        );
+
+    for (rom, status) in completed {
+        let mut item = report::Item {
+            name: rom.name.clone(),
+            status,
+            detail: String::new(),
+        };
+
+        if args.verify {
+            let path = Path::new(output_dir).join(rom.local_path());
+            let dat_roms = roms_by_stem.get(&rom.name).cloned().unwrap_or_default();
+
+            match verify::verify_file(&path, &rom.file, &dat_roms) {
+                verify::Verification::Verified => item.status = report::Status::Verified,
+                verify::Verification::Failed(reason) => {
+                    say!(
+                        "{}",
+                        format!("Verification failed for {}: {}", rom.name, reason).red()
+                    );
+                    item.status = report::Status::VerificationFailed;
+                    item.detail = reason;
+                }
+                verify::Verification::Unknown => {
+                    item.detail = "no hashes in DAT-file".to_string();
+                }
+            }
+        }
+
+        items.push(item);
+    }
+
+    for rom in failed_roms.iter() {
+        items.push(report::Item {
+            name: rom.name.clone(),
+            status: report::Status::Failed,
+            detail: String::new(),
+        });
+    }
+    for rom in remaining_roms.iter() {
+        items.push(report::Item {
+            name: rom.name.clone(),
+            status: report::Status::Failed,
+            detail: "interrupted".to_string(),
+        });
+    }
+    for name in missing_roms {
+        items.push(report::Item {
+            name: name.clone(),
+            status: report::Status::MissingAtServer,
+            detail: String::new(),
+        });
+    }
+
+    if args.m3u {
+        let all_roms: Vec<myrient::Rom> = summary
+            .downloaded_roms
+            .iter()
+            .chain(summary.already_downloaded_roms.iter())
+            .chain(failed_roms.iter())
+            .chain(remaining_roms.iter())
+            .cloned()
+            .collect();
+        let completed: Vec<myrient::Rom> = summary
+            .downloaded_roms
+            .iter()
+            .chain(summary.already_downloaded_roms.iter())
+            .cloned()
+            .collect();
+
+        match discs::write_playlists(
+            &all_roms,
+            &completed,
+            output_dir,
+            args.disc_folders,
+            args.transfer.sanitize,
+        ) {
+            Ok(paths) if !paths.is_empty() => say!(
+                "{}",
+                format!("Wrote {} .m3u playlists", paths.len()).green()
+            ),
+            Ok(_) => {}
+            Err(e) => say!("{}", format!("Error writing .m3u playlists: {}", e).red()),
+        }
+    }
+
+    let run_report = report::Report {
+        system: system.to_string(),
+        found_count: summary.completed_count() + failed_roms.len() + remaining_roms.len(),
+        items,
+    };
+
+    if args.verify {
+        say!(
+            "{}",
+            format!(
+                "Amount of verified ROMs             : {}",
+                run_report.count(report::Status::Verified)
+            )
+            .green()
+        );
+        let failed_count = run_report.count(report::Status::VerificationFailed);
+        if failed_count > 0 {
+            say!(
+                "{}",
+                format!("Amount of ROMs failing verification : {}", failed_count).red()
+            );
+        }
+    }
+
+    let report_dir = args.report_dir.as_deref().unwrap_or(output_dir);
+    let base_name = report::base_name(system);
+
+    if args.write_dats {
+        if wanted_games.is_empty() {
+            say!(
+                "{}",
+                "Have and miss DAT-files can only be written for DAT-file input.".yellow()
+            );
+        } else {
+            let have_names: HashSet<&str> = run_report
+                .items
+                .iter()
+                .filter(|item| {
+                    matches!(
+                        item.status,
+                        report::Status::Downloaded
+                            | report::Status::AlreadyDownloaded
+                            | report::Status::Verified
+                    )
+                })
+                .map(|item| item.name.as_str())
+                .collect();
+
+            // a game is had only when all of its files are
+            let (have_games, miss_games): (Vec<dat::Game>, Vec<dat::Game>) =
+                wanted_games.iter().cloned().partition(|game| {
+                    have_names.contains(game.name.as_str())
+                        || game
+                            .roms
+                            .iter()
+                            .all(|rom| have_names.contains(dat::file_stem(&rom.name).as_str()))
+                });
+
+            for (kind, games) in [("have", &have_games), ("miss", &miss_games)] {
+                let name = format!("{} ({})", base_name, kind);
+                let path = Path::new(report_dir).join(format!("{}.dat", name));
+
+                match dat::write_dat(&path, &name, &name, games) {
+                    Ok(_) => say!(
+                        "{}",
+                        format!("Wrote {} games to {}", games.len(), path.display()).green()
+                    ),
+                    Err(e) => say!(
+                        "{}",
+                        format!("Error writing {}: {}", path.display(), e).red()
+                    ),
+                }
+            }
+        }
+    }
+
+    if !args.report.is_empty() {
+        match report::write(&run_report, Path::new(report_dir), &base_name, &args.report) {
+            Ok(paths) => {
+                for path in paths {
+                    say!("{}", format!("Wrote report to {}", path.display()).green());
+                }
+            }
+            Err(e) => say!("{}", format!("Error writing report: {}", e).red()),
+        }
+    }
+
+    if !args.export.is_empty() {
+        let descriptions: HashMap<String, String> = wanted_games
+            .iter()
+            .flat_map(|game| {
+                let name = game.description.clone().unwrap_or(game.name.clone());
+                game.roms
+                    .iter()
+                    .map(|rom| dat::file_stem(&rom.name))
+                    .chain([game.name.clone()])
+                    .map(move |stem| (stem, name.clone()))
+            })
+            .collect();
+
+        let completed: Vec<myrient::Rom> = summary
+            .downloaded_roms
+            .iter()
+            .chain(summary.already_downloaded_roms.iter())
+            .cloned()
+            .collect();
+
+        // disc sets with an .m3u are listed once by their playlist
+        let mut playlists: HashMap<String, export::Entry> = HashMap::new();
+        for set in discs::find_sets(&completed) {
+            let path = discs::playlist_path(&set, args.disc_folders, args.transfer.sanitize);
+            if !Path::new(output_dir).join(&path).is_file() {
+                continue;
+            }
+            let first_disc = &set.roms[0].name;
+            let name = descriptions.get(first_disc).unwrap_or(first_disc);
+            let entry = export::Entry {
+                path,
+                name: tags::without_disc(name),
+                crc: None,
+            };
+            for rom in set.roms.iter() {
+                playlists.insert(rom.url.clone(), entry.clone());
+            }
+        }
+
+        let mut listed: HashSet<String> = HashSet::new();
+        let entries: Vec<export::Entry> = completed
+            .iter()
+            .filter_map(|rom| match playlists.get(&rom.url) {
+                Some(entry) => listed.insert(entry.path.clone()).then(|| entry.clone()),
+                None => Some(export::Entry {
+                    path: rom.local_path().to_string(),
+                    name: descriptions.get(&rom.name).unwrap_or(&rom.name).clone(),
+                    // only files of a single ROM can be matched by CRC. For
+                    // zips this is the CRC of the uncompressed ROM on purpose,
+                    // RetroArch matches its database by the content CRC.
+                    crc: match roms_by_stem.get(&rom.name).map(Vec::as_slice) {
+                        Some([dat_rom]) => dat_rom.crc.clone(),
+                        _ => None,
+                    },
+                }),
+            })
+            .collect();
+
+        for format in args.export.iter() {
+            let result = match format {
+                export::Format::Gamelist => export::write_gamelist(Path::new(output_dir), &entries),
+                export::Format::Lpl => export::write_playlist(
+                    Path::new(output_dir),
+                    &sanitize::file_name(&format!("{}.lpl", base_name), args.transfer.sanitize),
+                    system,
+                    &entries,
+                ),
+            };
+
+            match result {
+                Ok(path) => say!("{}", format!("Updated {}", path.display()).green()),
+                Err(e) => say!(
+                    "{}",
+                    format!("Error writing {:?} export: {}", format, e).red()
+                ),
+            }
+        }
+    }
+
+    if !args.manifest.is_empty() {
+        let completed: Vec<myrient::Rom> = summary
+            .downloaded_roms
+            .iter()
+            .chain(summary.already_downloaded_roms.iter())
+            .cloned()
+            .collect();
+        write_manifests(args, output_dir, &completed, &roms_by_stem);
+    }
+}
+
+// checksum manifests of the completed files in each of their folders
+fn write_manifests(
+    args: &DownloadArgs,
+    output_dir: &str,
+    completed: &[myrient::Rom],
+    roms_by_stem: &HashMap<String, Vec<dat::DatRom>>,
+) {
+    let mut folders: BTreeMap<&str, Vec<BTreeMap<String, String>>> = BTreeMap::new();
+
+    for rom in completed {
+        let local_path = rom.local_path();
+        let (folder, name) = local_path.rsplit_once('/').unwrap_or(("", local_path));
+
+        // the DAT only has the hashes of files that are not archives of the ROMs
+        let dat_rom = roms_by_stem
+            .get(&rom.name)
+            .and_then(|dat_roms| dat_roms.iter().find(|dat_rom| dat_rom.name == rom.file));
+        let from_dat = |format: &manifest::Format| dat_rom.and_then(|rom| format.dat_hash(rom));
+
+        let hashes = if args
+            .manifest
+            .iter()
+            .all(|format| from_dat(format).is_some())
+        {
+            None
+        } else {
+            match verify::hash_file(&Path::new(output_dir).join(local_path)) {
+                Ok(hashes) => Some(hashes),
+                Err(e) => {
+                    say!("{}", format!("Error hashing {}: {}", local_path, e).red());
+                    continue;
+                }
+            }
+        };
+
+        let entries = folders
+            .entry(folder)
+            .or_insert_with(|| vec![BTreeMap::new(); args.manifest.len()]);
+        for (format, entries) in args.manifest.iter().zip(entries.iter_mut()) {
+            let hash =
+                from_dat(format).or_else(|| hashes.as_ref().map(|hashes| format.hash(hashes)));
+            if let Some(hash) = hash {
+                entries.insert(name.to_string(), hash);
+            }
+        }
+    }
+
+    for (folder, entries) in folders {
+        for (format, entries) in args.manifest.iter().zip(entries.iter()) {
+            let dir = Path::new(output_dir).join(folder);
+            match manifest::write(&dir, *format, entries, args.transfer.sanitize) {
+                Ok(path) => say!("{}", format!("Updated {}", path.display()).green()),
+                Err(e) => say!(
+                    "{}",
+                    format!("Error writing {} manifest: {}", format.extension(), e).red()
+                ),
+            }
+        }
+    }
+}
+
+fn validate_args(args: &DownloadArgs) {
+    if let Some(input) = &args.input {
+        if !Path::new(input).is_file() {
+            say!("{}", "Invalid input DAT-file!".red());
+            output::exit(1);
+        }
+
+        if wanted_list::is_wanted_list(input) && args.collection.is_none() {
+            say!("{}", "Wanted-lists require --collection!".red());
+            output::exit(1);
+        }
+
+        if wanted_list::is_wanted_list(input) && args.update {
+            say!("{}", "Wanted-lists can not be used with --update!".red());
+            output::exit(1);
+        }
+
+        if wanted_list::is_wanted_list(input) && args.browse {
+            say!("{}", "Wanted-lists can not be used with --browse!".red());
+            output::exit(1);
+        }
+    }
+
+    if args.input.is_none() && !args.retry_failed && !args.browse {
+        say!(
+            "{}",
+            "No input given, use --input or set input in the config!".red()
+        );
+        output::exit(1);
+    }
+
+    match &args.output {
+        None => {
+            say!(
+                "{}",
+                "No output path given, use --output or set output in the config!".red()
+            );
+            output::exit(1);
+        }
+        Some(output) if !Path::new(output).is_dir() => {
+            say!("{}", "Invalid output ROM path!".red());
+            output::exit(1);
+        }
+        Some(_) => {}
+    }
+
+    if cache::is_offline() && !args.dry_run {
+        say!(
+            "{}",
+            "Downloading is not possible with --offline, use --dry-run to see the plan!".red()
+        );
+        output::exit(1);
+    }
+
+    if let Some(report_dir) = &args.report_dir {
+        if !Path::new(report_dir).is_dir() {
+            say!("{}", "Invalid report path!".red());
+            output::exit(1);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use colored::Colorize;
+use indicatif::DecimalBytes;
+
+use super::{fetch_or_exit, get_catalog_url, get_collection_url};
+use crate::myrient;
+use crate::output;
+use crate::say;
+use crate::ListArgs;
+
+pub fn run(args: ListArgs) {
+    if args.catalog_name.is_none() && !args.catalog {
+        for catalog in myrient::get_catalogs(&fetch_or_exit("")) {
+            print_listed(&catalog.title, &catalog.url, None);
+        }
+        return;
+    }
+
+    let catalog_url = get_catalog_url(
+        args.catalog_name.as_deref().unwrap_or_default(),
+        &args.catalog,
+    );
+
+    if args.collection.is_none() && !args.system {
+        for collection in myrient::get_collections(&fetch_or_exit(&catalog_url)) {
+            print_listed(
+                &collection.title,
+                &format!("{}{}", catalog_url, collection.url),
+                None,
+            );
+        }
+        return;
+    }
+
+    let collection_url = get_collection_url(
+        &catalog_url,
+        args.collection.as_deref().unwrap_or_default(),
+        &args.system,
+    );
+    let url_prefix = format!("{}{}", catalog_url, collection_url);
+
+    let mut roms: Vec<myrient::Rom> = myrient::get_roms_for_collection(&fetch_or_exit(&url_prefix))
+        .into_values()
+        .collect();
+    roms.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for rom in roms.iter() {
+        print_listed(&rom.file, &format!("{}{}", url_prefix, rom.url), rom.size);
+    }
+
+    say!(
+        "{}",
+        format!("Amount of files in collection       : {}", roms.len()).green()
+    );
+}
+
+// prints a catalog, collection or file on the server with its full URL
+fn print_listed(name: &str, url: &str, size: Option<u64>) {
+    match size {
+        Some(size) => say!(
+            "{} {}",
+            name.cyan(),
+            format!("({})", DecimalBytes(size)).green()
+        ),
+        None => say!("{}", name.cyan()),
+    }
+
+    output::emit(output::Event::Listed {
+        name: name.to_string(),
+        url: format!("{}{}", myrient::base_url(), url),
+        size,
+    });
+}
@@ -0,0 +1,215 @@
+pub mod diff;
+pub mod download;
+pub mod list;
+pub mod prune;
+pub mod search;
+pub mod status;
+pub mod verify;
+
+use std::io;
+
+use colored::Colorize;
+
+use crate::dat;
+use crate::myrient;
+use crate::output;
+use crate::say;
+
+// reads the system name, catalog name and games from a DAT-file
+pub fn load_dat(input: &str) -> (String, String, Vec<dat::Game>) {
+    say!("{}", "Opening input DAT-file...".green());
+    let dat_file_res = std::fs::read_to_string(input);
+    if dat_file_res.is_err() {
+        say!("{}", "Error opening DAT-file!".red());
+        output::exit(1);
+    }
+
+    let dat_file: String = dat_file_res.unwrap();
+    let dat_res = dat::parse(&dat_file);
+    if dat_res.is_err() {
+        say!("{}", "Error parsing DAT-file!".red());
+        output::exit(1);
+    }
+
+    let dat = dat_res.unwrap();
+
+    let (system, catalog_name) = dat::get_header_data(&dat);
+
+    (
+        system.unwrap_or_default(),
+        catalog_name.unwrap_or_default(),
+        dat::get_games(&dat),
+    )
+}
+
+// value of an option that can also be set in the config
+pub fn required_or_exit(value: Option<String>, name: &str) -> String {
+    value.unwrap_or_else(|| {
+        say!(
+            "{}",
+            format!(
+                "No {} given, use --{} or set {} in the config!",
+                name, name, name
+            )
+            .red()
+        );
+        output::exit(1);
+    })
+}
+
+pub fn fetch_or_exit(path: &str) -> String {
+    myrient::fetch(path).unwrap_or_else(|e| {
+        say!("{}", e);
+        output::exit(1);
+    })
+}
+
+pub fn confirm_or_exit(question: &str) {
+    output::prompt(&format!("{} [y/N]: ", question).cyan().to_string());
+
+    let mut answer = String::new();
+    let _ = io::stdin().read_line(&mut answer);
+
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        say!("{}", "Cancelled.".yellow());
+        output::exit(0);
+    }
+}
+
+pub fn get_catalog_url(catalog_name: &str, select_catalog: &bool) -> String {
+    let res = myrient::fetch("");
+
+    let html = res.unwrap_or_else(|e| {
+        say!("{}", e);
+        output::exit(1);
+    });
+
+    let mut catalog_url: Option<String> = None;
+
+    if !catalog_name.is_empty() {
+        catalog_url = myrient::get_catalog_url_by_name(&html, catalog_name);
+    }
+
+    if catalog_url.is_none() || *select_catalog {
+        // logger('Catalog for DAT not automatically found, please select from the following:', 'yellow')
+        say!(
+            "{}",
+            "Catalog for DAT not automatically found, please select from the following:".yellow()
+        );
+        let catalogs = myrient::get_catalogs(&html);
+
+        for index in 0..catalogs.len() {
+            say!(
+                "{}",
+                format!("{}: {}", index + 1, catalogs.get(index).unwrap().title).cyan()
+            );
+        }
+
+        loop {
+            output::prompt(&"Input selected catalog number: ".cyan().to_string());
+            let mut catalog_choice = String::new();
+            let _ = io::stdin().read_line(&mut catalog_choice);
+            let num_test = catalog_choice.trim().parse::<usize>();
+
+            match num_test {
+                Ok(num) => {
+                    if num > 0 && num <= catalogs.len() {
+                        return catalogs[num - 1].url.to_string();
+                    } else {
+                        say!("{}", "Input number out of range!".red());
+                    }
+                }
+                Err(_) => {
+                    say!("{}", "Invalid number!".red());
+                }
+            }
+        }
+    }
+
+    catalog_url.unwrap()
+}
+
+pub fn get_collection_url(catalog_url: &str, system_name: &str, select_system: &bool) -> String {
+    let res = myrient::fetch(catalog_url);
+    let html = res.unwrap_or_else(|e| {
+        say!("{}", e);
+        output::exit(1);
+    });
+
+    let collections = myrient::get_collections(&html);
+    let collections_len = collections.len();
+    let mut matching_collections: Vec<myrient::Collection> = Vec::new();
+
+    let mut collection_url: Option<String> = None;
+
+    if !system_name.is_empty() {
+        for collection in collections.clone() {
+            if collection.title.contains(system_name) {
+                matching_collections.push(collection)
+            }
+        }
+
+        if matching_collections.len() == 1 {
+            collection_url = Some(matching_collections[0].url.to_string());
+        }
+    }
+
+    if collection_url.is_none() || *select_system {
+        say!(
+            "{}",
+            "Collection for DAT not automatically found, please select from the following:"
+                .yellow()
+        );
+
+        let use_matches = matching_collections.len() > 1 && !*select_system;
+
+        if use_matches {
+            for index in 0..matching_collections.len() {
+                say!(
+                    "{}",
+                    format!(
+                        "{}: {}",
+                        index + 1,
+                        matching_collections.get(index).unwrap().title
+                    )
+                    .yellow()
+                );
+            }
+        } else {
+            for index in 0..collections_len {
+                say!(
+                    "{}",
+                    format!("{}: {}", index + 1, collections.get(index).unwrap().title).cyan()
+                );
+            }
+        }
+
+        loop {
+            output::prompt(&"Input selected collection number: ".cyan().to_string());
+            let mut collection_choice = String::new();
+            let _ = io::stdin().read_line(&mut collection_choice);
+            let num_test = collection_choice.trim().parse::<usize>();
+
+            match num_test {
+                Ok(num) => {
+                    if use_matches {
+                        if num > 0 && num <= matching_collections.len() {
+                            return matching_collections[num - 1].url.to_string();
+                        } else {
+                            say!("{}", "Input number out of range!".red());
+                        }
+                    } else if num > 0 && num <= collections_len {
+                        return collections[num - 1].url.to_string();
+                    } else {
+                        say!("{}", "Input number out of range!".red());
+                    }
+                }
+                Err(_) => {
+                    say!("{}", "Invalid number!".red());
+                }
+            }
+        }
+    }
+
+    collection_url.unwrap()
+}
@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+
+use super::{confirm_or_exit, load_dat, required_or_exit};
+use crate::constants;
+use crate::output;
+use crate::prune;
+use crate::say;
+use crate::state;
+use crate::PruneArgs;
+
+pub fn run(args: PruneArgs) {
+    let input = required_or_exit(args.input, "input");
+    let output = required_or_exit(args.output, "output");
+
+    if !Path::new(&input).is_file() {
+        say!("{}", "Invalid input DAT-file!".red());
+        output::exit(1);
+    }
+    if !Path::new(&output).is_dir() {
+        say!("{}", "Invalid ROM path!".red());
+        output::exit(1);
+    }
+
+    let (system, _, games) = load_dat(&input);
+    // files are named after the system of the last download, which may be a collection
+    let system = state::load_synced(&output).map_or(system, |(synced, _)| synced.name);
+    let output_dir = Path::new(&output);
+    let quarantine_dir = match &args.quarantine_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => output_dir
+            .join(constants::STATE_DIR)
+            .join(constants::QUARANTINE_DIR),
+    };
+
+    let names = state::load_names(&output).unwrap_or_default();
+    let candidates: Vec<prune::Candidate> =
+        prune::find(output_dir, &system, &games, &names, args.verify)
+            .unwrap_or_else(|e| {
+                say!("{}", format!("Error reading ROM path: {}", e).red());
+                output::exit(1);
+            })
+            .into_iter()
+            // files already in quarantine stay there
+            .filter(|candidate| !candidate.path.starts_with(&quarantine_dir))
+            .collect();
+
+    for candidate in candidates.iter() {
+        let line = match &candidate.detail {
+            Some(detail) => format!(
+                "{:12} {}: {}",
+                candidate.category,
+                candidate.path.display(),
+                detail
+            ),
+            None => format!("{:12} {}", candidate.category, candidate.path.display()),
+        };
+        match candidate.category {
+            prune::Category::NotInDat => say!("{}", line.yellow()),
+            prune::Category::FailedVerification => say!("{}", line.red()),
+        }
+    }
+
+    let count = |category| {
+        candidates
+            .iter()
+            .filter(|candidate| candidate.category == category)
+            .count()
+    };
+    say!(
+        "{}",
+        format!(
+            "Amount of files not in DAT          : {}",
+            count(prune::Category::NotInDat)
+        )
+        .green()
+    );
+    if args.verify {
+        say!(
+            "{}",
+            format!(
+                "Amount of files failing verification: {}",
+                count(prune::Category::FailedVerification)
+            )
+            .green()
+        );
+    }
+
+    if candidates.is_empty() || args.dry_run {
+        for candidate in candidates {
+            emit_pruned(&candidate, "listed");
+        }
+        return;
+    }
+
+    if !args.yes {
+        if args.delete {
+            confirm_or_exit(&format!("Delete these {} files?", candidates.len()));
+        } else {
+            confirm_or_exit(&format!(
+                "Move these {} files to {}?",
+                candidates.len(),
+                quarantine_dir.display()
+            ));
+        }
+    }
+
+    let mut errors = 0;
+    for candidate in candidates {
+        let result = if args.delete {
+            fs::remove_file(&candidate.path).map(|_| "deleted")
+        } else {
+            prune::quarantine(output_dir, &candidate.path, &quarantine_dir).map(|_| "quarantined")
+        };
+
+        match result {
+            Ok(action) => emit_pruned(&candidate, action),
+            Err(e) => {
+                errors += 1;
+                say!(
+                    "{}",
+                    format!("Error pruning {}: {}", candidate.path.display(), e).red()
+                );
+            }
+        }
+    }
+
+    if errors > 0 {
+        output::exit(1);
+    }
+
+    say!(
+        "{}",
+        if args.delete {
+            "Pruned files deleted.".to_string()
+        } else {
+            format!("Pruned files moved to {}", quarantine_dir.display())
+        }
+        .green()
+    );
+}
+
+fn emit_pruned(candidate: &prune::Candidate, action: &str) {
+    output::emit(output::Event::Pruned {
+        path: candidate.path.display().to_string(),
+        reason: match candidate.category {
+            prune::Category::NotInDat => "not_in_dat".to_string(),
+            prune::Category::FailedVerification => "verification_failed".to_string(),
+        },
+        action: action.to_string(),
+        detail: candidate.detail.clone(),
+    });
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use colored::Colorize;
+use indicatif::DecimalBytes;
+
+use super::download::{
+    build_retry_policy, build_throttle, ensure_free_space, exit_if_interrupted,
+    handle_download_result, prepare_paths,
+};
+use super::fetch_or_exit;
+use crate::cache;
+use crate::index;
+use crate::myrient;
+use crate::output;
+use crate::plan;
+use crate::say;
+use crate::SearchArgs;
+
+pub fn run(args: SearchArgs) {
+    if let Some(output) = &args.output {
+        if cache::is_offline() {
+            say!("{}", "Downloading is not possible with --offline!".red());
+            output::exit(1);
+        }
+        if !Path::new(output).is_dir() {
+            say!("{}", "Invalid output ROM path!".red());
+            output::exit(1);
+        }
+    }
+
+    let max_age = Duration::from_secs(args.max_age * 60 * 60);
+    let catalog_filter = args.catalog_name.as_deref().unwrap_or_default();
+    let collection_filter = args.collection.as_deref().unwrap_or_default();
+
+    let catalogs: Vec<myrient::Catalog> = myrient::get_catalogs(&fetch_or_exit(""))
+        .into_iter()
+        .filter(|catalog| contains_ignore_case(&catalog.title, catalog_filter))
+        .collect();
+
+    if catalogs.is_empty() {
+        say!("{}", "No matching catalogs found!".red());
+        output::exit(1);
+    }
+
+    let mut entries: Vec<index::Entry> = Vec::new();
+
+    for catalog in catalogs.iter() {
+        let cached = index::load(&catalog.title)
+            .filter(|cached| cache::is_offline() || cached.age() < max_age);
+
+        let catalog_index = match cached {
+            Some(cached) if !args.refresh => cached,
+            _ => match index::build(catalog) {
+                Ok(built) => {
+                    if let Err(e) = index::save(&catalog.title, &built) {
+                        say!("{}", format!("Error saving search index: {}", e).red());
+                    }
+                    built
+                }
+                Err(e) => {
+                    say!("{}", format!("Skipping {}: {}", catalog.title, e).yellow());
+                    continue;
+                }
+            },
+        };
+
+        entries.extend(
+            catalog_index
+                .entries
+                .into_iter()
+                .filter(|entry| contains_ignore_case(&entry.collection, collection_filter)),
+        );
+    }
+
+    let mut results = index::search(&entries, &args.query);
+    results.sort_by(|a, b| {
+        (&a.catalog, &a.collection, &a.rom.name).cmp(&(&b.catalog, &b.collection, &b.rom.name))
+    });
+
+    let width = results.len().checked_ilog10().unwrap_or(0) as usize + 1;
+
+    for (number, entry) in results.iter().enumerate() {
+        let size = entry
+            .rom
+            .size
+            .map_or(String::new(), |size| format!(" ({})", DecimalBytes(size)));
+        say!(
+            "{}{}",
+            format!("{:>width$}: {}", number + 1, entry.rom.file).cyan(),
+            size.green()
+        );
+        say!("{:width$}  {} / {}", "", entry.catalog, entry.collection);
+        say!("{:width$}  {}{}", "", myrient::base_url(), entry.url());
+
+        output::emit(output::Event::Found {
+            name: entry.rom.file.clone(),
+            catalog: entry.catalog.clone(),
+            collection: entry.collection.clone(),
+            url: format!("{}{}", myrient::base_url(), entry.url()),
+            size: entry.rom.size,
+        });
+    }
+
+    say!(
+        "{}",
+        format!("Amount of matching files            : {}", results.len()).green()
+    );
+
+    let output_dir = match &args.output {
+        Some(output_dir) if !results.is_empty() => output_dir,
+        _ => return,
+    };
+
+    let selected: Vec<usize> = if args.yes {
+        (0..results.len()).collect()
+    } else {
+        select_or_exit(results.len())
+    };
+
+    // URLs relative to the server root, so files from different collections
+    // can be downloaded and retried together
+    let mut roms: Vec<myrient::Rom> = selected
+        .into_iter()
+        .map(|index| myrient::Rom {
+            url: results[index].url(),
+            ..results[index].rom.clone()
+        })
+        .collect();
+
+    prepare_paths(&mut roms, &args.transfer, output_dir, true);
+
+    let retry_policy = build_retry_policy(&args.transfer);
+    let throttle = build_throttle(&args.transfer);
+
+    let plan = plan::build(&roms, output_dir, "", &HashMap::new(), false);
+    plan::print_summary(&plan);
+    ensure_free_space(&plan, output_dir, args.transfer.ignore_free_space);
+
+    let result = myrient::download_roms(&roms, output_dir, "", "", &retry_policy, &throttle);
+    handle_download_result(&result, output_dir, "", "");
+    exit_if_interrupted(&result);
+}
+
+fn contains_ignore_case(text: &str, part: &str) -> bool {
+    text.to_lowercase().contains(&part.to_lowercase())
+}
+
+// asks for result numbers such as "1,3-5", exiting on an empty answer
+fn select_or_exit(count: usize) -> Vec<usize> {
+    loop {
+        output::prompt(
+            &"Select files to download, e.g. 1,3-5 (empty to cancel): "
+                .cyan()
+                .to_string(),
+        );
+        let mut answer = String::new();
+        let _ = io::stdin().read_line(&mut answer);
+
+        if answer.trim().is_empty() {
+            say!("{}", "Cancelled.".yellow());
+            output::exit(0);
+        }
+
+        match parse_selection(&answer, count) {
+            Ok(selected) => return selected,
+            Err(e) => say!("{}", e.red()),
+        }
+    }
+}
+
+// parses 1-based numbers and ranges into 0-based indexes
+fn parse_selection(text: &str, count: usize) -> Result<Vec<usize>, String> {
+    let mut selected: Vec<usize> = Vec::new();
+
+    for part in text
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        let parse = |number: &str| {
+            number
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|number| (1..=count).contains(number))
+                .ok_or_else(|| format!("Invalid number: {}", number.trim()))
+        };
+
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start > end {
+            return Err(format!("Invalid range: {}", part));
+        }
+
+        for number in start..=end {
+            if !selected.contains(&(number - 1)) {
+                selected.push(number - 1);
+            }
+        }
+    }
+
+    Ok(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_numbers_and_ranges() {
+        assert_eq!(parse_selection("1, 3-5", 5), Ok(vec![0, 2, 3, 4]));
+        assert_eq!(parse_selection("2,2,1-2", 5), Ok(vec![1, 0]));
+        assert_eq!(parse_selection("4-4,", 5), Ok(vec![3]));
+    }
+
+    #[test]
+    fn rejects_numbers_out_of_range() {
+        assert!(parse_selection("0", 5).is_err());
+        assert!(parse_selection("6", 5).is_err());
+        assert!(parse_selection("2-9", 5).is_err());
+        assert!(parse_selection("a", 5).is_err());
+    }
+
+    #[test]
+    fn rejects_reversed_ranges() {
+        assert_eq!(
+            parse_selection("5-3", 5),
+            Err("Invalid range: 5-3".to_string())
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::io;
+use std::path::Path;
+
+use colored::Colorize;
+
+use super::diff::describe_version;
+use super::required_or_exit;
+use crate::constants;
+use crate::output;
+use crate::say;
+use crate::state;
+use crate::verify;
+use crate::StatusArgs;
+
+pub fn run(args: StatusArgs) {
+    let output = required_or_exit(args.output, "output");
+
+    if !Path::new(&output).is_dir() {
+        say!("{}", "Invalid output ROM path!".red());
+        output::exit(1);
+    }
+
+    match state::load_failed_roms(&output) {
+        Ok(failed) => {
+            // downloads from search results have URLs relative to the server root
+            if !failed.catalog_url.is_empty() {
+                say!(
+                    "{}",
+                    format!(
+                        "Collection                          : {}{}",
+                        failed.catalog_url, failed.collection_url
+                    )
+                    .green()
+                );
+            }
+            say!(
+                "{}",
+                format!(
+                    "Amount of unfinished ROMs           : {}",
+                    failed.roms.len()
+                )
+                .yellow()
+            );
+
+            for rom in failed.roms {
+                say!("{}", rom.name.yellow());
+                output::emit(output::Event::Rom {
+                    name: rom.name,
+                    status: output::RomStatus::Failed,
+                    size: rom.size,
+                    detail: None,
+                });
+            }
+
+            say!(
+                "{}",
+                "Run download with --retry-failed to continue.".yellow()
+            );
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            say!("{}", "No unfinished downloads.".green())
+        }
+        Err(e) => {
+            say!(
+                "{}",
+                format!("Error reading previously failed ROMs: {}", e).red()
+            );
+            output::exit(1);
+        }
+    }
+
+    if let Ok((synced, _)) = state::load_synced(&output) {
+        say!(
+            "{}",
+            format!(
+                "Synced DAT-file                     : {} ({})",
+                synced.name,
+                describe_version(&synced.version, &synced.date)
+            )
+            .green()
+        );
+        output::emit(output::Event::Synced {
+            name: synced.name,
+            version: synced.version,
+            date: synced.date,
+        });
+    }
+
+    let mut names: Vec<(String, String)> = state::load_names(&output)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    if !names.is_empty() {
+        names.sort();
+        say!(
+            "{}",
+            format!("Amount of renamed files             : {}", names.len()).green()
+        );
+        for (path, local_path) in names {
+            say!("{} -> {}", path, local_path);
+            output::emit(output::Event::Renamed { path, local_path });
+        }
+    }
+
+    let quarantine_dir = Path::new(&output)
+        .join(constants::STATE_DIR)
+        .join(constants::QUARANTINE_DIR);
+    let mut quarantined = verify::list_files(&quarantine_dir).unwrap_or_default();
+    if !quarantined.is_empty() {
+        quarantined.sort();
+        say!(
+            "{}",
+            format!(
+                "Amount of quarantined files         : {}",
+                quarantined.len()
+            )
+            .yellow()
+        );
+        for path in quarantined {
+            let path = path
+                .strip_prefix(&quarantine_dir)
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            say!("{}", path.yellow());
+            output::emit(output::Event::Quarantined { path });
+        }
+    }
+}
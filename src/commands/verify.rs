@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::Path;
+
+use colored::Colorize;
+
+use super::{load_dat, required_or_exit};
+use crate::manifest;
+use crate::output;
+use crate::say;
+use crate::state;
+use crate::verify;
+use crate::VerifyArgs;
+
+pub fn run(args: VerifyArgs) {
+    if let Some(manifest) = &args.manifest {
+        return verify_manifest(manifest, args.output.as_deref(), args.quiet);
+    }
+
+    let input = required_or_exit(args.input, "input");
+    let output = required_or_exit(args.output, "output");
+
+    if !Path::new(&input).is_file() {
+        say!("{}", "Invalid input DAT-file!".red());
+        output::exit(1);
+    }
+    if !Path::new(&output).is_dir() {
+        say!("{}", "Invalid ROM path!".red());
+        output::exit(1);
+    }
+
+    let (_, _, games) = load_dat(&input);
+
+    let names = state::load_names(&output).unwrap_or_default();
+    let checks = verify::verify_folder(Path::new(&output), &games, &names).unwrap_or_else(|e| {
+        say!("{}", format!("Error reading ROM path: {}", e).red());
+        output::exit(1);
+    });
+
+    print_checks(checks, args.quiet);
+}
+
+fn verify_manifest(manifest: &str, output: Option<&str>, quiet: bool) {
+    let path = Path::new(manifest);
+    let format = manifest::Format::from_path(path).unwrap_or_else(|| {
+        say!(
+            "{}",
+            "Unknown manifest, expected an .sfv, .md5 or .sha1 file!".red()
+        );
+        output::exit(1);
+    });
+    let text = fs::read_to_string(path).unwrap_or_else(|e| {
+        say!("{}", format!("Error reading manifest: {}", e).red());
+        output::exit(1);
+    });
+
+    let dir = match output {
+        Some(output) => Path::new(output),
+        None => path.parent().unwrap_or(Path::new(".")),
+    };
+    if !dir.is_dir() {
+        say!("{}", "Invalid ROM path!".red());
+        output::exit(1);
+    }
+
+    let entries = manifest::parse(&text, format);
+    if entries.is_empty() {
+        say!("{}", "No files listed in the manifest!".red());
+        output::exit(1);
+    }
+
+    print_checks(manifest::verify(dir, &entries, format), quiet);
+}
+
+fn print_checks(checks: Vec<verify::FileCheck>, quiet: bool) {
+    let mut verified_count = 0;
+    let mut unverified_count = 0;
+    let mut failed_count = 0;
+    let mut missing_count = 0;
+
+    for check in checks {
+        let (status, detail) = match (&check.path, check.verification) {
+            (None, _) => {
+                missing_count += 1;
+                say!("{}", format!("{:12} {}", "Missing", check.name).yellow());
+                (output::RomStatus::Missing, None)
+            }
+            (Some(_), verify::Verification::Verified) => {
+                verified_count += 1;
+                if !quiet {
+                    say!("{}", format!("{:12} {}", "Verified", check.name).green());
+                }
+                (output::RomStatus::Verified, None)
+            }
+            (Some(_), verify::Verification::Unknown) => {
+                unverified_count += 1;
+                if !quiet {
+                    say!("{}", format!("{:12} {}", "Unverified", check.name).cyan());
+                }
+                (output::RomStatus::Unverified, None)
+            }
+            (Some(_), verify::Verification::Failed(reason)) => {
+                failed_count += 1;
+                say!(
+                    "{}",
+                    format!("{:12} {}: {}", "Failed", check.name, reason).red()
+                );
+                (output::RomStatus::VerificationFailed, Some(reason))
+            }
+        };
+
+        output::emit(output::Event::Rom {
+            name: check.name,
+            status,
+            size: None,
+            detail,
+        });
+    }
+
+    say!(
+        "{}",
+        format!("Amount of verified ROMs             : {}", verified_count).green()
+    );
+    if unverified_count > 0 {
+        say!(
+            "{}",
+            format!("Amount of ROMs without hashes       : {}", unverified_count).cyan()
+        );
+    }
+    if missing_count > 0 {
+        say!(
+            "{}",
+            format!("Amount of missing ROMs              : {}", missing_count).yellow()
+        );
+    }
+    if failed_count > 0 {
+        say!(
+            "{}",
+            format!("Amount of ROMs failing verification : {}", failed_count).red()
+        );
+        output::exit(1);
+    }
+}
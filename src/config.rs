@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::constants;
+
+// Defaults for command line options, all optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    pub input: Option<String>,
+    pub output: Option<String>,
+    pub catalog: Option<String>,
+    pub collection: Option<String>,
+    /// Server address instead of Myrient's
+    pub base_url: Option<String>,
+    pub layout: Option<String>,
+    pub disc_folders: Option<bool>,
+    pub m3u: Option<bool>,
+    pub rebuild: Option<bool>,
+
+    pub retries: Option<usize>,
+    pub retry_delay: Option<u64>,
+    pub retry_max_delay: Option<u64>,
+    pub retry_jitter: Option<f64>,
+    pub limit_rate: Option<String>,
+    pub schedule: Option<Vec<String>>,
+    pub sanitize: Option<String>,
+    pub on_collision: Option<String>,
+
+    pub include_region: Option<Vec<String>>,
+    pub exclude_region: Option<Vec<String>>,
+    pub include_language: Option<Vec<String>>,
+    pub exclude_language: Option<Vec<String>>,
+    pub include_tag: Option<Vec<String>>,
+    pub exclude_tag: Option<Vec<String>>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub include_file: Option<Vec<String>>,
+    pub exclude_file: Option<Vec<String>>,
+    pub include_category: Option<Vec<String>>,
+    pub exclude_category: Option<Vec<String>>,
+    #[serde(rename = "1g1r")]
+    pub one_game_one_rom: Option<bool>,
+    pub region_priority: Option<Vec<String>>,
+    pub language_priority: Option<Vec<String>>,
+
+    /// Collection to use for a system name in the DAT-file
+    pub collections: HashMap<String, String>,
+}
+
+/// Config file in the user config directory
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(constants::CONFIG_DIR).join(constants::CONFIG_FILE))
+}
+
+/// Loads the settings from `path`, or the default config file if it exists.
+/// Settings of the given profile (`[profile.<name>]`) override the top-level
+/// ones, with collection mappings merged.
+pub fn load(path: Option<&str>, profile: Option<&str>) -> Result<Settings, String> {
+    let (path, explicit) = match path {
+        Some(path) => (PathBuf::from(path), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => (PathBuf::new(), false),
+        },
+    };
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !explicit && e.kind() == io::ErrorKind::NotFound && profile.is_none() => {
+            return Ok(Settings::default());
+        }
+        Err(e) => return Err(format!("Error reading config {}: {}", path.display(), e)),
+    };
+
+    let mut root: toml::Table = text
+        .parse()
+        .map_err(|e| format!("Error parsing config {}: {}", path.display(), e))?;
+
+    let profiles = root.remove("profile");
+
+    if let Some(name) = profile {
+        let table = profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .and_then(|profile| profile.as_table())
+            .ok_or_else(|| format!("Profile {} not found in {}", name, path.display()))?;
+
+        for (key, value) in table {
+            match (root.get_mut(key), value) {
+                (Some(toml::Value::Table(base)), toml::Value::Table(overrides))
+                    if key == "collections" =>
+                {
+                    base.extend(overrides.clone());
+                }
+                _ => {
+                    root.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    Settings::deserialize(toml::Value::Table(root))
+        .map_err(|e| format!("Error parsing config {}: {}", path.display(), e))
+}
@@ -1,6 +1,7 @@
 use once_cell::sync::Lazy;
 use reqwest::header;
 use std::collections::HashMap;
+use std::time::Duration;
 
 // Myrient HTTP-server addresses
 pub static MYRIENT_HTTP_ADDR: &str = "https://myrient.erista.me/files/";
@@ -24,3 +25,37 @@ pub static REQ_HEADERS: Lazy<header::HeaderMap> = Lazy::new(|| {
 
     headers
 });
+
+// Directory inside the output path where run state is kept
+pub static STATE_DIR: &str = ".auto-myrient";
+
+// File inside the state directory listing ROMs that failed in the last run
+pub static FAILED_ROMS_FILE: &str = "failed.json";
+
+// File inside the state directory mapping server file paths to renamed local ones
+pub static NAMES_FILE: &str = "names.json";
+
+// Files inside the state directory with the DAT-file the output was last synced to
+pub static SYNCED_FILE: &str = "synced.json";
+pub static SYNCED_DAT_FILE: &str = "synced.dat";
+
+// Folder inside the state directory for files moved away by prune
+pub static QUARANTINE_DIR: &str = "quarantine";
+
+// Directory inside the user cache directory for data shared between runs
+pub static CACHE_DIR: &str = "auto-myrient";
+
+// Directory inside the cache directory for search indexes, one file per catalog
+pub static INDEX_DIR: &str = "index";
+
+// Directory inside the cache directory for server listings
+pub static LISTINGS_DIR: &str = "listings";
+
+// How long cached listings are used before revalidating them with the server
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Directory inside the user config directory for the config file
+pub static CONFIG_DIR: &str = "auto-myrient";
+
+// Config file with defaults and profiles
+pub static CONFIG_FILE: &str = "config.toml";
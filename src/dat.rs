@@ -1,39 +1,104 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
 use std::path::Path;
 
+use chrono::Local;
 use colored::Colorize;
 use roxmltree::{Document, Error, ParsingOptions};
 
 use crate::constants;
+use crate::say;
 
-pub fn parse(dat_str: &str) -> Result<Document, Error> {
-    let mut opt = ParsingOptions::default();
-    opt.allow_dtd = true;
-    return Document::parse_with_options(dat_str, opt);
+pub fn parse(dat_str: &str) -> Result<Document<'_>, Error> {
+    let opt = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    Document::parse_with_options(dat_str, opt)
 }
 
-pub fn get_wanted_roms(dat: &Document) -> Vec<String> {
-    let mut wanted_roms: Vec<String> = Vec::new();
+#[derive(Debug, Clone)]
+pub struct DatRom {
+    /// File name of the ROM, including extension
+    pub name: String,
+    pub size: Option<u64>,
+    /// Hashes in lowercase hex
+    pub crc: Option<String>,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub name: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    /// Name of the parent game in Parent/Clone DATs
+    pub cloneof: Option<String>,
+    pub roms: Vec<DatRom>,
+}
+
+pub fn get_games(dat: &Document) -> Vec<Game> {
+    let mut games: Vec<Game> = Vec::new();
 
     let dat_root = dat.root_element();
     for child in dat_root.children() {
-        if child.is_element() && child.tag_name().name() == "game" {
-            for leaf in child.children() {
-                if leaf.is_element() && leaf.tag_name().name() == "rom" {
-                    match leaf.attribute("name") {
-                        None => continue,
-                        Some(name) => {
-                            let filename = Path::new(&name)
-                                .file_stem()
-                                .unwrap()
-                                .to_string_lossy()
-                                .to_string();
-
-                            if !wanted_roms.contains(&filename) {
-                                wanted_roms.push(filename);
-                            }
-                        }
+        if !child.is_element() || child.tag_name().name() != "game" {
+            continue;
+        }
+
+        let mut game = Game {
+            name: child.attribute("name").unwrap_or_default().to_string(),
+            description: None,
+            category: None,
+            cloneof: child.attribute("cloneof").map(String::from),
+            roms: Vec::new(),
+        };
+
+        for leaf in child.children() {
+            if !leaf.is_element() {
+                continue;
+            }
+
+            match leaf.tag_name().name() {
+                "category" => game.category = leaf.text().map(String::from),
+                "description" => game.description = leaf.text().map(String::from),
+                "rom" => match leaf.attribute("name") {
+                    None => continue,
+                    Some(name) => {
+                        let hash = |attribute| leaf.attribute(attribute).map(str::to_lowercase);
+
+                        game.roms.push(DatRom {
+                            name: name.to_string(),
+                            size: leaf.attribute("size").and_then(|size| size.parse().ok()),
+                            crc: hash("crc"),
+                            md5: hash("md5"),
+                            sha1: hash("sha1"),
+                        });
                     }
-                }
+                },
+                _ => {}
+            }
+        }
+
+        games.push(game);
+    }
+
+    games
+}
+
+/// Names of the wanted ROM files without extensions, without duplicates
+pub fn get_wanted_roms(games: &[Game]) -> Vec<String> {
+    let mut wanted_roms: Vec<String> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for game in games {
+        for rom in game.roms.iter() {
+            let filename = file_stem(&rom.name);
+
+            if seen.insert(filename.clone()) {
+                wanted_roms.push(filename);
             }
         }
     }
@@ -41,6 +106,67 @@ pub fn get_wanted_roms(dat: &Document) -> Vec<String> {
     wanted_roms
 }
 
+pub fn file_stem(name: &str) -> String {
+    Path::new(name)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// DAT entries expected in each server file, keyed by file name without
+/// extension. Files named after a game contain all of its ROMs.
+pub fn get_roms_by_stem(games: &[Game]) -> HashMap<String, Vec<DatRom>> {
+    let mut roms: HashMap<String, Vec<DatRom>> = HashMap::new();
+
+    for game in games {
+        for rom in game.roms.iter() {
+            let stem = file_stem(&rom.name);
+            if stem != game.name {
+                roms.entry(stem).or_default().push(rom.clone());
+            }
+        }
+
+        roms.entry(game.name.clone())
+            .or_default()
+            .extend(game.roms.iter().cloned());
+    }
+
+    roms
+}
+
+/// Sizes of all ROM files in the DAT, keyed by file name. They are the sizes
+/// of server files only when those are not archives of the ROMs.
+pub fn get_rom_sizes(games: &[Game]) -> HashMap<String, u64> {
+    let mut sizes: HashMap<String, u64> = HashMap::new();
+
+    for game in games {
+        for rom in game.roms.iter() {
+            if let Some(size) = rom.size {
+                sizes.insert(rom.name.clone(), size);
+            }
+        }
+    }
+
+    sizes
+}
+
+/// Version and date from the DAT header, e.g. "20240712-123456"
+pub fn get_version(dat: &Document) -> (Option<String>, Option<String>) {
+    let header = dat
+        .root_element()
+        .children()
+        .find(|child| child.has_tag_name("header"));
+    let field = |name: &str| {
+        header
+            .and_then(|header| header.children().find(|child| child.has_tag_name(name)))
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+    };
+
+    (field("version"), field("date"))
+}
+
 pub fn get_header_data(dat: &Document) -> (Option<String>, Option<String>) {
     let dat_root = dat.root_element();
     for child in dat_root.children() {
@@ -78,12 +204,12 @@ pub fn get_header_data(dat: &Document) -> (Option<String>, Option<String>) {
 
             match catalog_name {
                 None => {
-                    println!("{}", format!("Processing {}...", system.green()).green());
+                    say!("{}", format!("Processing {}...", system.green()).green());
 
                     return (Some(system), None);
                 }
                 Some(catalog_name) => {
-                    println!(
+                    say!(
                         "{}",
                         format!("Processing {}: {}...", catalog_name.green(), system.green(),)
                             .green()
@@ -97,3 +223,70 @@ pub fn get_header_data(dat: &Document) -> (Option<String>, Option<String>) {
 
     (None, None)
 }
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Writes games as a Logiqx DAT, loadable by e.g. RomVault and clrmamepro
+pub fn write_dat(path: &Path, name: &str, description: &str, games: &[Game]) -> io::Result<()> {
+    let mut xml = String::new();
+
+    xml.push_str("<?xml version=\"1.0\"?>\n");
+    xml.push_str("<!DOCTYPE datafile PUBLIC \"-//Logiqx//DTD ROM Management Datafile//EN\" \"http://www.logiqx.com/Dats/datafile.dtd\">\n");
+    xml.push_str("<datafile>\n");
+    xml.push_str("\t<header>\n");
+    xml.push_str(&format!("\t\t<name>{}</name>\n", escape_xml(name)));
+    xml.push_str(&format!(
+        "\t\t<description>{}</description>\n",
+        escape_xml(description)
+    ));
+    xml.push_str(&format!(
+        "\t\t<date>{}</date>\n",
+        Local::now().format("%Y-%m-%d %H-%M-%S")
+    ));
+    xml.push_str("\t\t<author>auto-myrient</author>\n");
+    xml.push_str("\t</header>\n");
+
+    for game in games {
+        xml.push_str(&format!("\t<game name=\"{}\"", escape_xml(&game.name)));
+        if let Some(cloneof) = &game.cloneof {
+            xml.push_str(&format!(" cloneof=\"{}\"", escape_xml(cloneof)));
+        }
+        xml.push_str(">\n");
+
+        if let Some(category) = &game.category {
+            xml.push_str(&format!(
+                "\t\t<category>{}</category>\n",
+                escape_xml(category)
+            ));
+        }
+        xml.push_str(&format!(
+            "\t\t<description>{}</description>\n",
+            escape_xml(game.description.as_ref().unwrap_or(&game.name))
+        ));
+
+        for rom in game.roms.iter() {
+            xml.push_str(&format!("\t\t<rom name=\"{}\"", escape_xml(&rom.name)));
+            if let Some(size) = rom.size {
+                xml.push_str(&format!(" size=\"{}\"", size));
+            }
+            for (attribute, value) in [("crc", &rom.crc), ("md5", &rom.md5), ("sha1", &rom.sha1)] {
+                if let Some(value) = value {
+                    xml.push_str(&format!(" {}=\"{}\"", attribute, value));
+                }
+            }
+            xml.push_str("/>\n");
+        }
+
+        xml.push_str("\t</game>\n");
+    }
+
+    xml.push_str("</datafile>\n");
+
+    fs::write(path, xml)
+}
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::dat::{DatRom, Game};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Added,
+    Removed,
+    /// Same contents under a new name
+    Renamed,
+    /// Same name with different files or hashes
+    Rehashed,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Kind::Added => "Added",
+            Kind::Removed => "Removed",
+            Kind::Renamed => "Renamed",
+            Kind::Rehashed => "Rehashed",
+        };
+        f.pad(text)
+    }
+}
+
+// A game that differs between two DATs
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub kind: Kind,
+    /// The game in the old DAT, if it was there
+    pub old: Option<Game>,
+    /// The game in the new DAT, if it is there
+    pub new: Option<Game>,
+}
+
+impl Change {
+    pub fn name(&self) -> &str {
+        match (&self.new, &self.old) {
+            (Some(game), _) | (None, Some(game)) => &game.name,
+            (None, None) => "",
+        }
+    }
+}
+
+type RomKey = (Option<u64>, Option<String>, Option<String>, Option<String>);
+
+fn rom_key(rom: &DatRom) -> RomKey {
+    (rom.size, rom.crc.clone(), rom.md5.clone(), rom.sha1.clone())
+}
+
+// files of a game with their names, to tell whether it changed
+fn contents(game: &Game) -> Vec<(String, RomKey)> {
+    let mut roms: Vec<(String, RomKey)> = game
+        .roms
+        .iter()
+        .map(|rom| (rom.name.clone(), rom_key(rom)))
+        .collect();
+    roms.sort();
+    roms
+}
+
+// files of a game without their names, to find it under another name
+fn fingerprint(game: &Game) -> Option<Vec<RomKey>> {
+    let has_hashes = game
+        .roms
+        .iter()
+        .all(|rom| rom.crc.is_some() || rom.md5.is_some() || rom.sha1.is_some());
+    if game.roms.is_empty() || !has_hashes {
+        return None;
+    }
+
+    let mut roms: Vec<RomKey> = game.roms.iter().map(rom_key).collect();
+    roms.sort();
+    Some(roms)
+}
+
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.chars().zip(b.chars()).take_while(|(a, b)| a == b).count()
+}
+
+// of removed games with the same files, the one whose name is most alike
+fn take_closest<'a>(games: &mut Vec<&'a Game>, name: &str) -> Option<&'a Game> {
+    let index = (0..games.len()).max_by_key(|&i| common_prefix(&games[i].name, name))?;
+    Some(games.remove(index))
+}
+
+/// Compares two versions of a DAT by game. Games only in one of them are
+/// paired as renamed when their files have the same sizes and hashes.
+pub fn compare(old: &[Game], new: &[Game]) -> Vec<Change> {
+    let old_by_name: HashMap<&str, &Game> =
+        old.iter().map(|game| (game.name.as_str(), game)).collect();
+    let new_names: HashSet<&str> = new.iter().map(|game| game.name.as_str()).collect();
+
+    let mut changes: Vec<Change> = Vec::new();
+
+    // removed games by their contents, to find renames
+    let mut removed: HashMap<Vec<RomKey>, Vec<&Game>> = HashMap::new();
+    let mut removed_names: HashSet<&str> = HashSet::new();
+    for game in old
+        .iter()
+        .filter(|game| !new_names.contains(game.name.as_str()))
+    {
+        removed_names.insert(&game.name);
+        if let Some(key) = fingerprint(game) {
+            removed.entry(key).or_default().push(game);
+        }
+    }
+
+    for game in new {
+        match old_by_name.get(game.name.as_str()) {
+            Some(old_game) => {
+                if contents(old_game) != contents(game) {
+                    changes.push(Change {
+                        kind: Kind::Rehashed,
+                        old: Some((*old_game).clone()),
+                        new: Some(game.clone()),
+                    });
+                }
+            }
+            None => {
+                let old_game = fingerprint(game)
+                    .and_then(|key| removed.get_mut(&key))
+                    .and_then(|games| take_closest(games, &game.name));
+
+                match old_game {
+                    Some(old_game) => {
+                        removed_names.remove(old_game.name.as_str());
+                        changes.push(Change {
+                            kind: Kind::Renamed,
+                            old: Some(old_game.clone()),
+                            new: Some(game.clone()),
+                        });
+                    }
+                    None => changes.push(Change {
+                        kind: Kind::Added,
+                        old: None,
+                        new: Some(game.clone()),
+                    }),
+                }
+            }
+        }
+    }
+
+    for game in old
+        .iter()
+        .filter(|game| removed_names.contains(game.name.as_str()))
+    {
+        changes.push(Change {
+            kind: Kind::Removed,
+            old: Some(game.clone()),
+            new: None,
+        });
+    }
+
+    changes.sort_by(|a, b| (a.kind, a.name()).cmp(&(b.kind, b.name())));
+
+    changes
+}
+
+pub fn count(changes: &[Change], kind: Kind) -> usize {
+    changes.iter().filter(|change| change.kind == kind).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(name: &str, crc: &str) -> Game {
+        Game {
+            name: name.to_string(),
+            description: None,
+            category: None,
+            cloneof: None,
+            roms: vec![DatRom {
+                name: format!("{}.nes", name),
+                size: Some(5),
+                crc: Some(crc.to_string()),
+                md5: None,
+                sha1: None,
+            }],
+        }
+    }
+
+    fn kinds(changes: &[Change]) -> Vec<(Kind, &str)> {
+        changes
+            .iter()
+            .map(|change| (change.kind, change.name()))
+            .collect()
+    }
+
+    #[test]
+    fn finds_added_removed_and_rehashed_games() {
+        let old = vec![
+            game("Kept", "1"),
+            game("Removed", "2"),
+            game("Changed", "3"),
+        ];
+        let new = vec![game("Kept", "1"), game("Changed", "4"), game("Added", "5")];
+        let changes = compare(&old, &new);
+
+        assert_eq!(
+            kinds(&changes),
+            [
+                (Kind::Added, "Added"),
+                (Kind::Removed, "Removed"),
+                (Kind::Rehashed, "Changed")
+            ]
+        );
+        assert_eq!(count(&changes, Kind::Added), 1);
+    }
+
+    #[test]
+    fn pairs_renamed_games_by_their_files() {
+        let old = vec![game("Game (USA)", "1"), game("Game (USA) (Rev 1)", "1")];
+        let new = vec![game("Game (USA) (Rev 1) (Fixed)", "1")];
+        let changes = compare(&old, &new);
+
+        assert_eq!(
+            kinds(&changes),
+            [
+                (Kind::Removed, "Game (USA)"),
+                (Kind::Renamed, "Game (USA) (Rev 1) (Fixed)")
+            ]
+        );
+        assert_eq!(
+            changes[1].old.as_ref().map(|game| game.name.as_str()),
+            Some("Game (USA) (Rev 1)")
+        );
+    }
+
+    #[test]
+    fn does_not_pair_games_without_hashes() {
+        let mut old = game("Old", "1");
+        let mut new = game("New", "1");
+        old.roms[0].crc = None;
+        new.roms[0].crc = None;
+
+        assert_eq!(
+            kinds(&compare(&[old], &[new])),
+            [(Kind::Added, "New"), (Kind::Removed, "Old")]
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::myrient::Rom;
+use crate::sanitize;
+use crate::tags;
+
+// Discs of one game, e.g. "Game (USA) (Disc 1)" and "Game (USA) (Disc 2)"
+#[derive(Debug, Clone)]
+pub struct DiscSet {
+    /// Name of the game without the disc tag
+    pub title: String,
+    /// Discs in order
+    pub roms: Vec<Rom>,
+}
+
+fn parent_dir(path: &str) -> &str {
+    path.rfind('/').map_or("", |slash| &path[..slash])
+}
+
+fn file_name(path: &str) -> &str {
+    path.rfind('/').map_or(path, |slash| &path[slash + 1..])
+}
+
+/// Groups the files of games with more than one disc in the same folder
+pub fn find_sets(roms: &[Rom]) -> Vec<DiscSet> {
+    let mut sets: HashMap<(String, String), DiscSet> = HashMap::new();
+
+    for rom in roms {
+        if tags::parse(&rom.name).disc.is_none() {
+            continue;
+        }
+
+        let title = tags::without_disc(&rom.name);
+        let dir = parent_dir(rom.local_path()).to_string();
+
+        sets.entry((dir, title.clone()))
+            .or_insert_with(|| DiscSet {
+                title,
+                roms: Vec::new(),
+            })
+            .roms
+            .push(rom.clone());
+    }
+
+    let mut sets: Vec<DiscSet> = sets
+        .into_values()
+        .filter(|set| set.roms.len() > 1)
+        .collect();
+
+    for set in sets.iter_mut() {
+        set.roms.sort_by_key(|rom| tags::parse(&rom.name).disc);
+    }
+    sets.sort_by(|a, b| a.title.cmp(&b.title));
+
+    sets
+}
+
+/// Moves the discs of each set into a folder named after the game
+pub fn apply_folders(roms: &mut [Rom]) -> usize {
+    let sets = find_sets(roms);
+
+    let folders: HashMap<String, String> = sets
+        .iter()
+        .flat_map(|set| {
+            set.roms
+                .iter()
+                .map(|rom| (rom.url.clone(), set.title.replace(['/', '\\'], "-")))
+        })
+        .collect();
+
+    for rom in roms.iter_mut() {
+        if let Some(folder) = folders.get(&rom.url) {
+            let path = rom.local_path();
+            rom.path = Some(match parent_dir(path) {
+                "" => format!("{}/{}", folder, file_name(path)),
+                dir => format!("{}/{}/{}", dir, folder, file_name(path)),
+            });
+        }
+    }
+
+    sets.len()
+}
+
+// folder of the playlist of a set, relative to the output folder
+fn playlist_dir(set: &DiscSet, in_folders: bool) -> &str {
+    let disc_dir = parent_dir(set.roms[0].local_path());
+    if in_folders {
+        parent_dir(disc_dir)
+    } else {
+        disc_dir
+    }
+}
+
+/// Path of the .m3u playlist of a set relative to the output folder, with /
+/// as the separator
+pub fn playlist_path(set: &DiscSet, in_folders: bool, profile: sanitize::Profile) -> String {
+    let name = sanitize::file_name(&format!("{}.m3u", set.title), profile);
+    match playlist_dir(set, in_folders) {
+        "" => name,
+        dir => format!("{}/{}", dir, name),
+    }
+}
+
+/// Writes an .m3u playlist for each set in `roms` whose discs are all in
+/// `completed`, next to the discs, or next to their folder with `in_folders`
+pub fn write_playlists(
+    roms: &[Rom],
+    completed: &[Rom],
+    output_dir: &str,
+    in_folders: bool,
+    profile: sanitize::Profile,
+) -> io::Result<Vec<PathBuf>> {
+    let completed: HashSet<&str> = completed.iter().map(|rom| rom.url.as_str()).collect();
+    let mut paths: Vec<PathBuf> = Vec::new();
+
+    for set in find_sets(roms) {
+        if !set
+            .roms
+            .iter()
+            .all(|rom| completed.contains(rom.url.as_str()))
+        {
+            continue;
+        }
+
+        let playlist_dir = playlist_dir(&set, in_folders);
+
+        let mut playlist = String::new();
+        for rom in set.roms.iter() {
+            let entry = rom.local_path()[playlist_dir.len()..].trim_start_matches('/');
+            playlist.push_str(entry);
+            playlist.push('\n');
+        }
+
+        let path = Path::new(output_dir).join(playlist_path(&set, in_folders, profile));
+        fs::write(&path, playlist)?;
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
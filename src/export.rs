@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{self, Path, PathBuf};
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use crate::dat;
+
+// Name of the EmulationStation and ES-DE game list in a system's ROM folder
+pub const GAMELIST_FILE: &str = "gamelist.xml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// EmulationStation and ES-DE gamelist.xml in the output folder
+    Gamelist,
+    /// RetroArch playlist named after the system
+    Lpl,
+}
+
+// A downloaded file for frontends
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Path relative to the output folder, with / as the separator
+    pub path: String,
+    /// Name of the game to show
+    pub name: String,
+    /// CRC32 of the ROM from the DAT, in lowercase hex
+    pub crc: Option<String>,
+}
+
+fn invalid_data(path: &Path, error: impl ToString) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), error.to_string()),
+    )
+}
+
+/// Adds the entries missing from gamelist.xml in the output folder. Games
+/// already listed are kept as they are, so scraped metadata is not lost.
+pub fn write_gamelist(output_dir: &Path, entries: &[Entry]) -> io::Result<PathBuf> {
+    let path = output_dir.join(GAMELIST_FILE);
+    let mut kept: Vec<String> = Vec::new();
+    let mut listed: HashSet<String> = HashSet::new();
+
+    if path.is_file() {
+        let text = fs::read_to_string(&path)?;
+        let doc = roxmltree::Document::parse(&text).map_err(|e| invalid_data(&path, e))?;
+
+        for node in doc
+            .root_element()
+            .children()
+            .filter(|node| node.is_element())
+        {
+            let game_path = node
+                .children()
+                .find(|child| child.has_tag_name("path"))
+                .and_then(|child| child.text());
+            if let Some(game_path) = game_path {
+                listed.insert(game_path.trim().trim_start_matches("./").to_string());
+            }
+
+            kept.push(text[node.range()].to_string());
+        }
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\"?>\n<gameList>\n");
+
+    for element in kept {
+        xml.push_str(&format!("\t{}\n", element));
+    }
+    for entry in entries.iter().filter(|entry| !listed.contains(&entry.path)) {
+        xml.push_str("\t<game>\n");
+        xml.push_str(&format!(
+            "\t\t<path>./{}</path>\n",
+            dat::escape_xml(&entry.path)
+        ));
+        xml.push_str(&format!(
+            "\t\t<name>{}</name>\n",
+            dat::escape_xml(&entry.name)
+        ));
+        xml.push_str("\t</game>\n");
+    }
+
+    xml.push_str("</gameList>\n");
+
+    fs::write(&path, xml)?;
+    Ok(path)
+}
+
+/// Adds the entries missing from the RetroArch playlist of the system. Paths
+/// are absolute, as RetroArch expects, and CRCs from the DAT let it match its
+/// database without scanning the files.
+pub fn write_playlist(
+    output_dir: &Path,
+    file_name: &str,
+    system: &str,
+    entries: &[Entry],
+) -> io::Result<PathBuf> {
+    let path = output_dir.join(file_name);
+    let rom_dir = path::absolute(output_dir)?;
+
+    let mut playlist = if path.is_file() {
+        serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| invalid_data(&path, e))?
+    } else {
+        json!({
+            "version": "1.5",
+            "default_core_path": "",
+            "default_core_name": "",
+            "label_display_mode": 0,
+            "right_thumbnail_mode": 0,
+            "left_thumbnail_mode": 0,
+            "sort_mode": 0,
+            "items": [],
+        })
+    };
+
+    let items = playlist
+        .get_mut("items")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| invalid_data(&path, "no items in playlist"))?;
+
+    let listed: HashSet<String> = items
+        .iter()
+        .filter_map(|item| item.get("path").and_then(Value::as_str))
+        .map(String::from)
+        .collect();
+
+    for entry in entries {
+        let rom_path = rom_dir.join(&entry.path).to_string_lossy().to_string();
+        if listed.contains(&rom_path) {
+            continue;
+        }
+
+        items.push(json!({
+            "path": rom_path,
+            "label": entry.name,
+            "core_path": "DETECT",
+            "core_name": "DETECT",
+            "crc32": entry
+                .crc
+                .as_ref()
+                .map_or("DETECT".to_string(), |crc| format!("{}|crc", crc.to_uppercase())),
+            "db_name": format!("{}.lpl", system),
+        }));
+    }
+
+    fs::write(&path, serde_json::to_string_pretty(&playlist)?)?;
+    Ok(path)
+}
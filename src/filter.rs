@@ -0,0 +1,127 @@
+use std::fs;
+
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+
+use crate::dat::Game;
+use crate::tags;
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn is_match(&self, name: &str) -> bool {
+        match self {
+            Pattern::Glob(glob) => glob.is_match(name),
+            Pattern::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+// Filters on games in the DAT, an empty list means no filtering
+#[derive(Debug, Clone, Default)]
+pub struct GameFilter {
+    pub include_regions: Vec<String>,
+    pub exclude_regions: Vec<String>,
+    pub include_languages: Vec<String>,
+    pub exclude_languages: Vec<String>,
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    /// Patterns matched against game names, see `parse_pattern`
+    pub include_patterns: Vec<Pattern>,
+    pub exclude_patterns: Vec<Pattern>,
+    pub include_categories: Vec<String>,
+    pub exclude_categories: Vec<String>,
+}
+
+impl GameFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include_regions.is_empty()
+            && self.exclude_regions.is_empty()
+            && self.include_languages.is_empty()
+            && self.exclude_languages.is_empty()
+            && self.include_tags.is_empty()
+            && self.exclude_tags.is_empty()
+            && self.include_patterns.is_empty()
+            && self.exclude_patterns.is_empty()
+            && self.include_categories.is_empty()
+            && self.exclude_categories.is_empty()
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        let tags = tags::parse(name);
+
+        let any_region = |regions: &Vec<String>| regions.iter().any(|r| tags.has_region(r));
+        let any_language = |languages: &Vec<String>| languages.iter().any(|l| tags.has_language(l));
+        let any_flag = |flags: &Vec<String>| flags.iter().any(|f| tags.has_flag(f));
+        let any_pattern = |patterns: &Vec<Pattern>| patterns.iter().any(|p| p.is_match(name));
+
+        (self.include_regions.is_empty() || any_region(&self.include_regions))
+            && !any_region(&self.exclude_regions)
+            && (self.include_languages.is_empty() || any_language(&self.include_languages))
+            && !any_language(&self.exclude_languages)
+            && (self.include_tags.is_empty() || any_flag(&self.include_tags))
+            && !any_flag(&self.exclude_tags)
+            && (self.include_patterns.is_empty() || any_pattern(&self.include_patterns))
+            && !any_pattern(&self.exclude_patterns)
+    }
+
+    fn matches_category(&self, category: &Option<String>) -> bool {
+        let any_category = |categories: &Vec<String>| match category {
+            None => false,
+            Some(category) => categories.iter().any(|c| c.eq_ignore_ascii_case(category)),
+        };
+
+        (self.include_categories.is_empty() || any_category(&self.include_categories))
+            && !any_category(&self.exclude_categories)
+    }
+
+    pub fn matches(&self, game: &Game) -> bool {
+        self.matches_category(&game.category) && self.matches_name(&game.name)
+    }
+}
+
+/// Parses a glob such as `*Mario*`, or a regex when prefixed with `re:`.
+/// Both are case-insensitive, globs have to match the whole name.
+pub fn parse_pattern(pattern: &str) -> Result<Pattern, String> {
+    match pattern.strip_prefix("re:") {
+        Some(regex) => RegexBuilder::new(regex)
+            .case_insensitive(true)
+            .build()
+            .map(Pattern::Regex)
+            .map_err(|e| format!("invalid regex '{}': {}", regex, e)),
+        None => GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(|glob| Pattern::Glob(glob.compile_matcher()))
+            .map_err(|e| format!("invalid glob '{}': {}", pattern, e)),
+    }
+}
+
+/// Reads patterns from a file, one per line. Empty lines and lines starting
+/// with `#` are skipped.
+pub fn read_patterns(path: &str) -> Result<Vec<Pattern>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Error reading pattern file {}: {}", path, e))?;
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| parse_pattern(line).map_err(|e| format!("{} in {}", e, path)))
+        .collect()
+}
+
+/// Keeps only the games matching the filter
+pub fn apply(games: Vec<Game>, filter: &GameFilter) -> Vec<Game> {
+    if filter.is_empty() {
+        return games;
+    }
+
+    games
+        .into_iter()
+        .filter(|game| filter.matches(game))
+        .collect()
+}
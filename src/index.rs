@@ -0,0 +1,134 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::cache;
+use crate::constants;
+use crate::myrient::{self, Catalog, FetchError, Rom};
+use crate::say;
+
+// A file on the server along with where it was found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub catalog: String,
+    pub collection: String,
+    pub catalog_url: String,
+    pub collection_url: String,
+    pub rom: Rom,
+}
+
+impl Entry {
+    /// URL of the file relative to the server root
+    pub fn url(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.catalog_url, self.collection_url, self.rom.url
+        )
+    }
+}
+
+// All files of one catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Index {
+    /// Seconds since the Unix epoch
+    pub created: u64,
+    pub entries: Vec<Entry>,
+}
+
+impl Index {
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(cache::unix_time().saturating_sub(self.created))
+    }
+}
+
+fn index_path(catalog: &str) -> Option<PathBuf> {
+    let file_name: String = catalog
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+
+    cache::cache_dir().map(|dir| {
+        dir.join(constants::INDEX_DIR)
+            .join(format!("{}.json", file_name))
+    })
+}
+
+pub fn load(catalog: &str) -> Option<Index> {
+    let text = fs::read_to_string(index_path(catalog)?).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+pub fn save(catalog: &str, index: &Index) -> io::Result<()> {
+    let path = index_path(catalog)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cache directory available"))?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, serde_json::to_string(index)?)
+}
+
+/// Walks every collection of the catalog. Collections that fail to load are
+/// skipped with a warning.
+pub fn build(catalog: &Catalog) -> Result<Index, FetchError> {
+    let collections = myrient::get_collections(&myrient::fetch(&catalog.url)?);
+    let mut entries: Vec<Entry> = Vec::new();
+
+    for (index, collection) in collections.iter().enumerate() {
+        say!(
+            "{}",
+            format!(
+                "Indexing {} {}/{}: {}",
+                catalog.title,
+                index + 1,
+                collections.len(),
+                collection.title
+            )
+            .cyan()
+        );
+
+        let html = match myrient::fetch(&format!("{}{}", catalog.url, collection.url)) {
+            Ok(html) => html,
+            Err(e) => {
+                say!(
+                    "{}",
+                    format!("Skipping {}: {}", collection.title, e).yellow()
+                );
+                continue;
+            }
+        };
+
+        for rom in myrient::get_roms_for_collection(&html).into_values() {
+            entries.push(Entry {
+                catalog: catalog.title.clone(),
+                collection: collection.title.clone(),
+                catalog_url: catalog.url.clone(),
+                collection_url: collection.url.clone(),
+                rom,
+            });
+        }
+    }
+
+    Ok(Index {
+        created: cache::unix_time(),
+        entries,
+    })
+}
+
+/// Entries whose file name contains every word, ignoring case
+pub fn search<'a>(entries: &'a [Entry], words: &[String]) -> Vec<&'a Entry> {
+    let words: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+
+    entries
+        .iter()
+        .filter(|entry| {
+            let name = entry.rom.name.to_lowercase();
+            words.iter().all(|word| name.contains(word.as_str()))
+        })
+        .collect()
+}
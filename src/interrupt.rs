@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+
+use crate::output;
+use crate::say;
+
+// Exit code for runs stopped by SIGINT/SIGTERM, as shells report them
+pub const EXIT_CODE_INTERRUPTED: i32 = 130;
+
+// How often interruptible sleeps check for signals
+const SLEEP_TICK: Duration = Duration::from_millis(200);
+
+static SIGNAL_COUNT: AtomicUsize = AtomicUsize::new(0);
+static TRANSFERRING: AtomicBool = AtomicBool::new(false);
+
+/// Marks downloads as in flight until dropped. Only then does the first
+/// signal wait for the current file, otherwise the run stops right away.
+pub struct Transfer;
+
+impl Transfer {
+    pub fn start() -> Transfer {
+        TRANSFERRING.store(true, Ordering::SeqCst);
+        Transfer
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        TRANSFERRING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Installs the SIGINT/SIGTERM handler. While downloading, the first signal asks
+/// the run to stop after checkpointing the current download and the second one
+/// aborts immediately. Otherwise, e.g. at prompts, the first signal aborts.
+pub fn install_handler() {
+    let res = ctrlc::set_handler(|| {
+        let count = SIGNAL_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if count == 1 && TRANSFERRING.load(Ordering::SeqCst) {
+            eprintln!(
+                "\n{}",
+                "Interrupted, stopping after saving progress on the current file. Interrupt again to abort immediately."
+                    .yellow()
+            );
+        } else {
+            // move past any half-drawn progress bars before leaving
+            eprintln!("\n{}", "Aborted!".red());
+            let _ = io::stderr().flush();
+            // prints the collected JSON events too
+            output::exit(EXIT_CODE_INTERRUPTED);
+        }
+    });
+
+    if let Err(e) = res {
+        say!(
+            "{}",
+            format!("Error installing interrupt handler: {}", e).red()
+        );
+    }
+}
+
+pub fn is_interrupted() -> bool {
+    SIGNAL_COUNT.load(Ordering::SeqCst) > 0
+}
+
+/// Sleeps for `duration`, waking up early if the run gets interrupted
+pub fn sleep(duration: Duration) {
+    let deadline = Instant::now() + duration;
+
+    while !is_interrupted() {
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+
+        thread::sleep(SLEEP_TICK.min(deadline - now));
+    }
+}
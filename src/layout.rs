@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use crate::dat::{self, Game};
+use crate::myrient::Rom;
+use crate::tags;
+
+const PLACEHOLDERS: &[&str] = &[
+    "system",
+    "catalog",
+    "region",
+    "first_letter",
+    "game",
+    "category",
+    "parent",
+    "file",
+];
+
+// used for placeholders without a value, e.g. games without a category
+const UNKNOWN: &str = "Unknown";
+
+// Piece of a layout template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+// splits a template into text and placeholders from left to right
+fn scan(text: &str) -> Result<Vec<Part<'_>>, String> {
+    let mut parts: Vec<Part> = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed {{ in layout {}", text))?;
+        let name = &rest[start + 1..start + end];
+
+        if rest[..start].contains('}') {
+            return Err(format!("Unmatched }} in layout {}", text));
+        }
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "Unknown placeholder {{{}}} in layout, expected one of: {}",
+                name,
+                PLACEHOLDERS.join(", ")
+            ));
+        }
+
+        parts.push(Part::Text(&rest[..start]));
+        parts.push(Part::Placeholder(name));
+        rest = &rest[start + end + 1..];
+    }
+
+    if rest.contains('}') {
+        return Err(format!("Unmatched }} in layout {}", text));
+    }
+    parts.push(Part::Text(rest));
+
+    Ok(parts)
+}
+
+/// Checks a layout template such as "{catalog}/{system}/{first_letter}/{file}"
+pub fn parse_template(text: &str) -> Result<String, String> {
+    scan(text)?;
+    Ok(text.to_string())
+}
+
+// last segment of a listing URL such as "files/No-Intro/" as its title
+fn url_title(url: &str) -> String {
+    let segment = url.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    let bytes = segment.as_bytes();
+    let mut decoded: Vec<u8> = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = segment
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// values become single path segments
+fn segment(value: &str) -> String {
+    let value = value.replace(['/', '\\'], "-");
+
+    if value.trim().is_empty() {
+        UNKNOWN.to_string()
+    } else {
+        value
+    }
+}
+
+fn first_letter(name: &str) -> String {
+    match tags::parse(name).title.chars().next() {
+        Some(c) if c.is_alphabetic() => c.to_uppercase().to_string(),
+        _ => "#".to_string(),
+    }
+}
+
+// fills in the placeholders in one pass, so values containing braces stay as they are
+fn render(template: &str, values: &HashMap<&str, String>) -> String {
+    let parts = scan(template).unwrap_or_default();
+    let mut path = String::new();
+
+    for part in parts.iter() {
+        match part {
+            Part::Text(text) => path.push_str(text),
+            Part::Placeholder(name) => path.push_str(&values[name]),
+        }
+    }
+
+    // the file goes into the laid out folder if not placed explicitly
+    if !parts.contains(&Part::Placeholder("file")) {
+        path = format!("{}/{}", path, values["file"]);
+    }
+
+    path.split('/')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Sets the path of each ROM in the output folder from the layout template.
+/// Values come from the DAT game the file belongs to, or from the file name for
+/// files not in a DAT. The system and catalog default to the collection and
+/// catalog of the URLs.
+pub fn apply(
+    roms: &mut [Rom],
+    template: &str,
+    system: &str,
+    catalog: &str,
+    catalog_url: &str,
+    collection_url: &str,
+    games: &[Game],
+) {
+    let mut games_by_stem: HashMap<String, &Game> = HashMap::new();
+    for game in games {
+        games_by_stem.insert(game.name.clone(), game);
+        for rom in game.roms.iter() {
+            games_by_stem
+                .entry(dat::file_stem(&rom.name))
+                .or_insert(game);
+        }
+    }
+
+    let system = match system {
+        "" => url_title(collection_url),
+        system => system.to_string(),
+    };
+    let catalog = match catalog {
+        "" => url_title(catalog_url),
+        catalog => catalog.to_string(),
+    };
+
+    for rom in roms.iter_mut() {
+        let game = games_by_stem.get(&rom.name);
+        let game_name = game.map_or(rom.name.as_str(), |game| game.name.as_str());
+
+        let values: HashMap<&str, String> = HashMap::from([
+            ("system", segment(&system)),
+            ("catalog", segment(&catalog)),
+            (
+                "region",
+                segment(&tags::parse(&rom.name).regions.join(", ")),
+            ),
+            ("first_letter", first_letter(game_name)),
+            ("game", segment(game_name)),
+            (
+                "category",
+                segment(game.and_then(|game| game.category.as_deref()).unwrap_or("")),
+            ),
+            (
+                "parent",
+                segment(
+                    game.and_then(|game| game.cloneof.as_deref())
+                        .unwrap_or(game_name),
+                ),
+            ),
+            ("file", segment(&rom.file)),
+        ]);
+
+        rom.path = Some(render(template, &values));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom(file: &str) -> Rom {
+        Rom {
+            name: dat::file_stem(file),
+            file: file.to_string(),
+            url: file.to_string(),
+            size: None,
+            path: None,
+        }
+    }
+
+    fn laid_out(template: &str, file: &str, games: &[Game]) -> String {
+        let mut roms = vec![rom(file)];
+        apply(
+            &mut roms,
+            template,
+            "",
+            "",
+            "files/No-Intro/",
+            "files/No-Intro/Nintendo%20-%20NES/",
+            games,
+        );
+        roms[0].local_path().to_string()
+    }
+
+    #[test]
+    fn accepts_known_placeholders() {
+        assert!(parse_template("{catalog}/{system}/{first_letter}/{file}").is_ok());
+        assert!(parse_template("Games").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(parse_template("{unknown}").is_err());
+        assert!(parse_template("{system").is_err());
+        assert!(parse_template("a}{file}").is_err());
+        assert!(parse_template("{file}}").is_err());
+    }
+
+    #[test]
+    fn fills_in_values_from_the_urls_and_name() {
+        assert_eq!(
+            laid_out(
+                "{catalog}/{system}/{region}/{first_letter}",
+                "game (USA).zip",
+                &[]
+            ),
+            "No-Intro/Nintendo - NES/USA/G/game (USA).zip"
+        );
+        assert_eq!(
+            laid_out("{first_letter}/{file}", "1942 (Japan).zip", &[]),
+            "#/1942 (Japan).zip"
+        );
+    }
+
+    #[test]
+    fn uses_the_game_from_the_dat() {
+        let games = vec![Game {
+            name: "Game (Europe)".to_string(),
+            description: None,
+            category: Some("Sports/Racing".to_string()),
+            cloneof: Some("Game (USA)".to_string()),
+            roms: Vec::new(),
+        }];
+
+        assert_eq!(
+            laid_out("{category}/{parent}/{game}", "Game (Europe).zip", &games),
+            "Sports-Racing/Game (USA)/Game (Europe)/Game (Europe).zip"
+        );
+        assert_eq!(
+            laid_out("{category}", "Other.zip", &games),
+            "Unknown/Other.zip"
+        );
+    }
+
+    #[test]
+    fn does_not_substitute_placeholders_in_values() {
+        let games = vec![Game {
+            name: "Game".to_string(),
+            description: None,
+            category: Some("{file} {system}".to_string()),
+            cloneof: None,
+            roms: Vec::new(),
+        }];
+
+        assert_eq!(
+            laid_out("{category}/{system}", "Game.zip", &games),
+            "{file} {system}/Nintendo - NES/Game.zip"
+        );
+    }
+}
@@ -1,26 +1,93 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
-use std::{
-    fmt::Debug,
-    io::{self, Write},
-    path::Path,
-};
+use std::{collections::HashMap, fmt::Debug, time::Duration};
 
+mod cache;
+mod collision;
+mod commands;
+mod config;
 mod constants;
 mod dat;
+mod diff;
+mod discs;
+mod export;
+mod filter;
+mod index;
+mod interrupt;
+mod layout;
+mod manifest;
 mod myrient;
+mod one_game_one_rom;
+mod output;
+mod plan;
+mod prune;
+mod rebuild;
+mod report;
+mod retry;
+mod sanitize;
+mod state;
+mod tags;
+mod throttle;
+mod tui;
+mod verify;
+mod wanted_list;
 
 /// Tool for bulk downloading from Myrient
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
-struct Args {
-    /// Input DAT-file containing wanted ROMs
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Format of the output, json and jsonl print machine-readable events to stdout
+    #[arg(long, global = true, value_enum, default_value_t = output::Format::Text)]
+    output_format: output::Format,
+
+    /// Use only cached server listings, without the network
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Hours to use cached server listings before checking them for changes
+    #[arg(long, global = true, default_value_t = 24)]
+    cache_ttl: u64,
+
+    /// Config file to use instead of config.toml in the user config directory
+    #[arg(long, global = true)]
+    config: Option<String>,
+
+    /// Profile in the config file to use, e.g. ps2 for [profile.ps2]
+    #[arg(long, global = true)]
+    profile: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Download the ROMs of a DAT-file or wanted-list
+    Download(Box<DownloadArgs>),
+    /// List catalogs, the collections of a catalog or the files of a collection
+    List(ListArgs),
+    /// Check a local folder against the sizes and hashes in a DAT-file
+    Verify(VerifyArgs),
+    /// Find files by name across catalogs and collections, optionally downloading them
+    Search(SearchArgs),
+    /// Show unfinished downloads saved in an output folder
+    Status(StatusArgs),
+    /// Move files not in a DAT-file out of a folder, or delete them
+    Prune(PruneArgs),
+    /// Compare two versions of a DAT-file
+    Diff(DiffArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct DownloadArgs {
+    /// Input DAT-file containing wanted ROMs, or a .txt/.csv list of wanted titles
     #[arg(short, long)]
-    input: String,
+    input: Option<String>,
 
     /// Output path for ROM files to be downloaded
     #[arg(short, long)]
-    output: String,
+    output: Option<String>,
 
     /// Choose catalog manually, even if automatically found
     #[arg(short, long)]
@@ -30,299 +97,618 @@ struct Args {
     #[arg(short, long)]
     system: bool,
 
-    /// List only ROMs that are not found in server (if any)
+    /// Choose the collection and files in a full-screen browser
+    #[arg(short, long, conflicts_with_all = ["catalog", "system"])]
+    browse: bool,
+
+    /// Name of the system collection to use, required for wanted-lists
+    #[arg(long)]
+    collection: Option<String>,
+
+    /// Name of the catalog to use, e.g. No-Intro or Redump
+    #[arg(long)]
+    catalog_name: Option<String>,
+
+    /// Download matches from a wanted-list without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Show the download plan with the status and size of each file without downloading
     #[arg(short, long)]
-    list: bool,
+    dry_run: bool,
+
+    /// Folders for the files in the output path, e.g. {catalog}/{system}/{first_letter}/{file},
+    /// with {system}, {catalog}, {region}, {first_letter}, {game}, {category}, {parent} and {file}
+    #[arg(long, value_parser = layout::parse_template)]
+    layout: Option<String>,
+
+    /// Put the discs of multi-disc games in a folder named after the game
+    #[arg(long)]
+    disc_folders: bool,
+
+    /// Write an .m3u playlist for each downloaded multi-disc game
+    #[arg(long)]
+    m3u: bool,
+
+    /// Rename local files matching wanted ROMs by size and CRC/SHA1 to their names in
+    /// the DAT-file instead of downloading them again
+    #[arg(long)]
+    rebuild: bool,
+
+    /// Download only the games added or changed since the DAT-file the output was last
+    /// completely downloaded from, moving outdated files to quarantine
+    #[arg(short, long, conflicts_with = "browse")]
+    update: bool,
+
+    /// With --update, also move the files of removed and renamed games to quarantine
+    #[arg(long, requires = "update")]
+    prune_removed: bool,
+
+    /// Retry only the ROMs that failed to download in the previous run
+    #[arg(short, long, conflicts_with_all = ["input", "catalog", "system", "browse", "dry_run", "update"])]
+    retry_failed: bool,
+
+    #[command(flatten)]
+    transfer: TransferArgs,
+
+    /// Verify downloaded ROMs against the sizes and hashes in the DAT-file
+    #[arg(long)]
+    verify: bool,
+
+    /// Write have and miss DAT-files of the output folder after downloading
+    #[arg(long)]
+    write_dats: bool,
+
+    /// Write a summary report after downloading, in any of md, html and csv
+    #[arg(long, value_delimiter = ',')]
+    report: Vec<report::Format>,
+
+    /// Add the downloaded files to frontend lists in the output path, any of gamelist
+    /// (EmulationStation and ES-DE gamelist.xml) and lpl (RetroArch playlist)
+    #[arg(long, value_delimiter = ',')]
+    export: Vec<export::Format>,
+
+    /// Write checksum manifests named after each folder with downloaded files, any of
+    /// sfv, md5 and sha1, with the hashes from the DAT-file or computed
+    #[arg(long, value_delimiter = ',')]
+    manifest: Vec<manifest::Format>,
+
+    /// Directory for the have/miss DAT-files and reports, defaults to the output path
+    #[arg(long)]
+    report_dir: Option<String>,
+
+    /// Only include ROMs for these regions, e.g. USA,Europe
+    #[arg(long, value_delimiter = ',', help_heading = "Filters")]
+    include_region: Vec<String>,
+
+    /// Exclude ROMs for these regions
+    #[arg(long, value_delimiter = ',', help_heading = "Filters")]
+    exclude_region: Vec<String>,
+
+    /// Only include ROMs in these languages, e.g. En,Fr (implied by region if not in the name)
+    #[arg(long, value_delimiter = ',', help_heading = "Filters")]
+    include_language: Vec<String>,
+
+    /// Exclude ROMs in these languages
+    #[arg(long, value_delimiter = ',', help_heading = "Filters")]
+    exclude_language: Vec<String>,
+
+    /// Only include ROMs with these tags, e.g. "Virtual Console"
+    #[arg(long, value_delimiter = ',', help_heading = "Filters")]
+    include_tag: Vec<String>,
+
+    /// Exclude ROMs with these tags, e.g. Beta,Proto,Demo,Unl
+    #[arg(long, value_delimiter = ',', help_heading = "Filters")]
+    exclude_tag: Vec<String>,
+
+    /// Only include games with names matching these globs, or regexes prefixed with re:
+    #[arg(long, value_parser = filter::parse_pattern, help_heading = "Filters")]
+    include: Vec<filter::Pattern>,
+
+    /// Exclude games with names matching these globs, or regexes prefixed with re:
+    #[arg(long, value_parser = filter::parse_pattern, help_heading = "Filters")]
+    exclude: Vec<filter::Pattern>,
+
+    /// Read include patterns from a file, one per line
+    #[arg(long, help_heading = "Filters")]
+    include_file: Vec<String>,
+
+    /// Read exclude patterns from a file, one per line
+    #[arg(long, help_heading = "Filters")]
+    exclude_file: Vec<String>,
+
+    /// Only include games in these DAT categories, e.g. Games
+    #[arg(long, value_delimiter = ',', help_heading = "Filters")]
+    include_category: Vec<String>,
+
+    /// Exclude games in these DAT categories, e.g. Demos,Applications
+    #[arg(long, value_delimiter = ',', help_heading = "Filters")]
+    exclude_category: Vec<String>,
+
+    /// Keep only one ROM per game (1G1R), chosen by region and language priority
+    #[arg(long = "1g1r", help_heading = "Filters")]
+    one_game_one_rom: bool,
+
+    /// Region priority for 1G1R, most preferred first
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "USA,World,Europe,UK,Canada,Australia,Japan",
+        help_heading = "Filters"
+    )]
+    region_priority: Vec<String>,
+
+    /// Language priority for 1G1R, most preferred first
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "En",
+        help_heading = "Filters"
+    )]
+    language_priority: Vec<String>,
+
+    /// Collections to use for system names in the DAT-file, from the config
+    #[arg(skip)]
+    collection_map: HashMap<String, String>,
 }
 
-fn main() {
-    let args = Args::parse();
+// Retry, speed and file name options shared by the commands that download
+#[derive(clap::Args, Debug)]
+struct TransferArgs {
+    /// Amount of retries for a failed download
+    #[arg(long, default_value_t = 3)]
+    retries: usize,
+
+    /// Base delay in milliseconds before retrying, doubled on each retry
+    #[arg(long, default_value_t = 100)]
+    retry_delay: u64,
+
+    /// Maximum delay in milliseconds between retries
+    #[arg(long, default_value_t = 30_000)]
+    retry_max_delay: u64,
+
+    /// Fraction (0.0 - 1.0) of the retry delay that is randomized
+    #[arg(long, default_value_t = 0.5, value_parser = retry::parse_jitter)]
+    retry_jitter: f64,
+
+    /// Limit total download speed, e.g. 500K or 2M (bytes per second)
+    #[arg(long, value_parser = throttle::parse_rate)]
+    limit_rate: Option<throttle::Rate>,
+
+    /// Download speed for a time of day, e.g. 01:00-07:00=unlimited or 18:00-23:00=pause
+    #[arg(long, value_parser = throttle::parse_window)]
+    schedule: Vec<throttle::RateWindow>,
+
+    /// Rules for file and folder names, windows-safe and fat32 for NTFS, exFAT and FAT32 drives
+    #[arg(long, value_enum, default_value_t = sanitize::Profile::Posix)]
+    sanitize: sanitize::Profile,
+
+    /// What to do with files that would overwrite each other on case-insensitive
+    /// or Unicode-normalizing filesystems
+    #[arg(long, value_enum, default_value_t = collision::Strategy::Suffix)]
+    on_collision: collision::Strategy,
+
+    /// Start downloading even if the output does not seem to have enough free space
+    #[arg(long)]
+    ignore_free_space: bool,
+}
 
-    validate_args(&args);
+#[derive(clap::Args, Debug)]
+struct ListArgs {
+    /// Name of the catalog to list the collections of, e.g. No-Intro or Redump
+    #[arg(long)]
+    catalog_name: Option<String>,
 
-    let mut output_dir = args.output;
-    if cfg!(windows) && output_dir.ends_with("\\") {
-        output_dir = output_dir[..output_dir.len() - 1].to_string();
-    } else if cfg!(unix) && output_dir.ends_with("/") {
-        output_dir = output_dir[..output_dir.len() - 1].to_string();
-    }
+    /// Name of the collection to list the files of
+    #[arg(long)]
+    collection: Option<String>,
 
-    println!("{}", format!("Output directory: {}", output_dir).green());
+    /// Choose catalog manually
+    #[arg(short, long)]
+    catalog: bool,
 
-    println!("{}", "Opening input DAT-file...".green());
-    let dat_file_res = std::fs::read_to_string(&args.input);
-    if dat_file_res.is_err() {
-        println!("{}", "Error opening DAT-file!".red());
-        std::process::exit(1);
-    }
+    /// Choose system collection manually
+    #[arg(short, long)]
+    system: bool,
+}
 
-    let dat_file: String = dat_file_res.unwrap();
-    let dat_res = dat::parse(&dat_file);
-    if dat_res.is_err() {
-        println!("{}", "Error parsing DAT-file!".red());
-        std::process::exit(1);
-    }
+#[derive(clap::Args, Debug)]
+struct VerifyArgs {
+    /// DAT-file to verify against
+    #[arg(short, long)]
+    input: Option<String>,
 
-    let dat = dat_res.unwrap();
+    /// Folder with the ROM files to verify, defaults to the folder of --manifest
+    #[arg(short, long)]
+    output: Option<String>,
 
-    let (system_res, catalog_name_res) = dat::get_header_data(&dat);
-    let system = system_res.unwrap_or(String::new());
-    let catalog_name = catalog_name_res.unwrap_or(String::new());
+    /// SFV, MD5 or SHA1 manifest to verify the files listed in it against, without a DAT-file
+    #[arg(short, long, conflicts_with = "input")]
+    manifest: Option<String>,
 
-    let catalog_url = get_catalog_url(&catalog_name, &args.catalog);
+    /// Only show files that are missing or fail verification
+    #[arg(short, long)]
+    quiet: bool,
+}
 
-    let collection_url = get_collection_url(&catalog_url, &system, &args.system);
+#[derive(clap::Args, Debug)]
+struct SearchArgs {
+    /// Words to look for in file names
+    #[arg(required = true)]
+    query: Vec<String>,
 
-    let collection_res = myrient::fetch(&format!("{}{}", &catalog_url, &collection_url));
-    let collection_html = collection_res.unwrap_or_else(|e| {
-        println!("{}", e);
-        std::process::exit(1);
-    });
+    /// Only search catalogs with this in their name, e.g. No-Intro or Redump
+    #[arg(long)]
+    catalog_name: Option<String>,
 
-    let wanted_rom_names = dat::get_wanted_roms(&dat);
-    let available_roms = myrient::get_roms_for_collection(&collection_html);
+    /// Only search collections with this in their name
+    #[arg(long)]
+    collection: Option<String>,
 
-    let mut missing_roms: Vec<String> = Vec::new();
-    let mut wanted_roms: Vec<myrient::Rom> = Vec::new();
+    /// Rebuild the cached index even if it is recent
+    #[arg(long)]
+    refresh: bool,
 
-    for wanted_rom_name in wanted_rom_names.iter() {
-        if available_roms.contains_key(wanted_rom_name) {
-            wanted_roms.push(available_roms.get(wanted_rom_name).unwrap().clone());
-        } else {
-            missing_roms.push(wanted_rom_name.to_string());
-        }
-    }
+    /// Hours before a cached index is rebuilt
+    #[arg(long, default_value_t = 168)]
+    max_age: u64,
 
-    let missing_roms_len = missing_roms.len();
+    /// Output path for downloading the selected results
+    #[arg(short, long)]
+    output: Option<String>,
 
-    println!(
-        "{}",
-        format!(
-            "Amount of wanted ROMs in DAT-file   : {}",
-            wanted_roms.len().to_string()
-        )
-        .green()
-    );
-    println!(
-        "{}",
-        format!(
-            "Amount of found ROMs at server      : {}",
-            available_roms.len().to_string()
-        )
-        .green()
-    );
-    if missing_roms_len > 0 {
-        println!(
-            "{}",
-            format!(
-                "Amount of missing ROMs at server    : {}",
-                missing_roms_len.to_string()
-            )
-            .yellow()
-        );
-    }
+    /// Download all results without asking which ones
+    #[arg(short, long, requires = "output")]
+    yes: bool,
 
-    if !args.list {
-        match myrient::download_roms(&wanted_roms, &output_dir, &catalog_url, &collection_url) {
-            Ok(_) => {
-                println!("{}", "All downloads successful!".green());
-            }
-            Err(err) => {
-                println!(
-                    "{}",
-                    format!(
-                        "Following {} ROMs failed to download:",
-                        err.failed_roms.len()
-                    )
-                    .red()
-                );
-
-                for rom in err.failed_roms {
-                    println!("{}", rom.name.red());
-                }
-            }
-        }
-    }
+    #[command(flatten)]
+    transfer: TransferArgs,
+}
 
-    if missing_roms_len > 0 {
-        println!(
-            "{}",
-            format!(
-                "Following {} ROMs in DAT not automatically found from server, grab these manually:",
-                missing_roms_len
-            )
-            .red()
-        );
-
-        for missing_rom in missing_roms.iter() {
-            println!("{}", missing_rom.yellow());
-        }
-    } else {
-        println!("{}", "All wanted ROMs found from server!".green());
-    }
+#[derive(clap::Args, Debug)]
+struct PruneArgs {
+    /// Current DAT-file of the folder
+    #[arg(short, long)]
+    input: Option<String>,
+
+    /// Folder with the ROM files to prune
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Also prune files in the DAT-file that fail verification
+    #[arg(long)]
+    verify: bool,
+
+    /// Folder to move pruned files to, defaults to .auto-myrient/quarantine in the output path
+    #[arg(long, conflicts_with = "delete")]
+    quarantine_dir: Option<String>,
+
+    /// Delete pruned files instead of moving them to quarantine
+    #[arg(long)]
+    delete: bool,
+
+    /// Only list the files that would be pruned
+    #[arg(short, long)]
+    dry_run: bool,
+
+    /// Prune without asking for confirmation
+    #[arg(short, long)]
+    yes: bool,
 }
 
-fn validate_args(args: &Args) {
-    if !Path::new(&args.input).is_file() {
-        println!("{}", "Invalid input DAT-file!".red());
-        std::process::exit(1);
-    }
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// Older DAT-file
+    old: String,
 
-    if !Path::new(&args.output).is_dir() {
-        println!("{}", "Invalid output ROM path!".red());
-        std::process::exit(1);
-    }
+    /// Newer DAT-file
+    new: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct StatusArgs {
+    /// Output folder of previous downloads
+    #[arg(short, long)]
+    output: Option<String>,
 }
 
-fn get_catalog_url(catalog_name: &String, select_catalog: &bool) -> String {
-    let res = myrient::fetch(&String::new());
+// sets an option from the config unless it was given on the command line
+macro_rules! set {
+    ($matches:expr, $field:expr, $id:literal, $value:expr) => {
+        if $matches.value_source($id) != Some(ValueSource::CommandLine) {
+            if let Some(value) = $value {
+                $field = value;
+            }
+        }
+    };
+}
 
-    let html = res.unwrap_or_else(|e| {
-        println!("{}", e);
-        std::process::exit(1);
+fn main() {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    output::set_format(cli.output_format);
+    interrupt::install_handler();
+    cache::configure(cache::Settings {
+        ttl: Duration::from_secs(cli.cache_ttl * 60 * 60),
+        offline: cli.offline,
     });
 
-    let mut catalog_url: Option<String> = None;
+    let config = config::load(cli.config.as_deref(), cli.profile.as_deref()).unwrap_or_else(|e| {
+        say!("{}", e.red());
+        output::exit(1);
+    });
 
-    if !catalog_name.is_empty() {
-        let url_res = myrient::get_catalog_url_by_name(&html, catalog_name);
-        if url_res.is_some() {
-            catalog_url = Some(url_res.unwrap().to_string());
-        }
+    if let Some(base_url) = &config.base_url {
+        myrient::set_base_url(base_url);
     }
 
-    if catalog_url.is_none() || *select_catalog {
-        // logger('Catalog for DAT not automatically found, please select from the following:', 'yellow')
-        println!(
-            "{}",
-            "Catalog for DAT not automatically found, please select from the following:".yellow()
-        );
-        let catalogs = myrient::get_catalogs(&html);
-
-        for index in 0..catalogs.len() {
-            println!(
-                "{}",
-                format!(
-                    "{}: {}",
-                    (index + 1).to_string(),
-                    catalogs.get(index).unwrap().title
-                )
-                .cyan()
-            );
+    let sub_matches = match matches.subcommand() {
+        Some((_, sub_matches)) => sub_matches,
+        None => unreachable!("a subcommand is required"),
+    };
+    let exit_on_error = |result: Result<(), String>| {
+        if let Err(e) = result {
+            say!("{}", e.red());
+            output::exit(1);
         }
+    };
 
-        loop {
-            print!("{}", "Input selected catalog number: ".cyan());
-            io::stdout().flush().unwrap();
-            let mut catalog_choice = String::new();
-            let _ = io::stdin().read_line(&mut catalog_choice);
-            let num_test = catalog_choice.trim().parse::<usize>();
-
-            match num_test {
-                Ok(num) => {
-                    if num > 0 && num <= catalogs.len() {
-                        return catalogs.get(&num - 1).unwrap().url.to_string();
-                    } else {
-                        println!("{}", "Input number out of range!".red());
-                    }
-                }
-                Err(_) => {
-                    println!("{}", "Invalid number!".red());
-                }
+    match cli.command {
+        Command::Download(mut args) => {
+            exit_on_error(apply_config(&mut args, sub_matches, config));
+            commands::download::run(*args)
+        }
+        Command::List(args) => commands::list::run(args),
+        Command::Verify(mut args) => {
+            // files listed in a manifest are checked in its folder
+            if args.manifest.is_none() {
+                set!(sub_matches, args.input, "input", config.input.map(Some));
+                set!(sub_matches, args.output, "output", config.output.map(Some));
             }
+            commands::verify::run(args)
+        }
+        Command::Search(mut args) => {
+            exit_on_error(apply_transfer_config(
+                &mut args.transfer,
+                sub_matches,
+                &config,
+            ));
+            commands::search::run(args)
+        }
+        Command::Status(mut args) => {
+            set!(sub_matches, args.output, "output", config.output.map(Some));
+            commands::status::run(args)
+        }
+        Command::Prune(mut args) => {
+            set!(sub_matches, args.input, "input", config.input.map(Some));
+            set!(sub_matches, args.output, "output", config.output.map(Some));
+            commands::prune::run(args)
         }
+        Command::Diff(args) => commands::diff::run(args),
     }
 
-    return catalog_url.unwrap();
+    output::finish();
 }
 
-fn get_collection_url(catalog_url: &String, system_name: &String, select_system: &bool) -> String {
-    let res = myrient::fetch(&catalog_url);
-    let html = res.unwrap_or_else(|e| {
-        println!("{}", e);
-        std::process::exit(1);
-    });
+// fills in the options not given on the command line from the config
+fn apply_config(
+    args: &mut DownloadArgs,
+    matches: &ArgMatches,
+    config: config::Settings,
+) -> Result<(), String> {
+    apply_transfer_config(&mut args.transfer, matches, &config)?;
+
+    let parse_patterns = |patterns: Option<Vec<String>>| {
+        patterns
+            .map(|patterns| {
+                patterns
+                    .iter()
+                    .map(|p| filter::parse_pattern(p))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()
+    };
+
+    set!(matches, args.input, "input", config.input.map(Some));
+    set!(matches, args.output, "output", config.output.map(Some));
+    set!(
+        matches,
+        args.catalog_name,
+        "catalog_name",
+        config.catalog.map(Some)
+    );
+    set!(
+        matches,
+        args.collection,
+        "collection",
+        config.collection.map(Some)
+    );
+    set!(
+        matches,
+        args.layout,
+        "layout",
+        config
+            .layout
+            .as_deref()
+            .map(layout::parse_template)
+            .transpose()?
+            .map(Some)
+    );
 
-    let collections = myrient::get_collections(&html);
-    let collections_len = collections.len();
-    let mut matching_collections: Vec<myrient::Collection> = Vec::new();
+    set!(
+        matches,
+        args.include_region,
+        "include_region",
+        config.include_region
+    );
+    set!(
+        matches,
+        args.exclude_region,
+        "exclude_region",
+        config.exclude_region
+    );
+    set!(
+        matches,
+        args.include_language,
+        "include_language",
+        config.include_language
+    );
+    set!(
+        matches,
+        args.exclude_language,
+        "exclude_language",
+        config.exclude_language
+    );
+    set!(matches, args.include_tag, "include_tag", config.include_tag);
+    set!(matches, args.exclude_tag, "exclude_tag", config.exclude_tag);
+    set!(
+        matches,
+        args.include,
+        "include",
+        parse_patterns(config.include)?
+    );
+    set!(
+        matches,
+        args.exclude,
+        "exclude",
+        parse_patterns(config.exclude)?
+    );
+    set!(
+        matches,
+        args.include_file,
+        "include_file",
+        config.include_file
+    );
+    set!(
+        matches,
+        args.exclude_file,
+        "exclude_file",
+        config.exclude_file
+    );
+    set!(
+        matches,
+        args.include_category,
+        "include_category",
+        config.include_category
+    );
+    set!(
+        matches,
+        args.exclude_category,
+        "exclude_category",
+        config.exclude_category
+    );
+    set!(
+        matches,
+        args.disc_folders,
+        "disc_folders",
+        config.disc_folders
+    );
+    set!(matches, args.m3u, "m3u", config.m3u);
+    set!(matches, args.rebuild, "rebuild", config.rebuild);
+    set!(
+        matches,
+        args.one_game_one_rom,
+        "one_game_one_rom",
+        config.one_game_one_rom
+    );
+    set!(
+        matches,
+        args.region_priority,
+        "region_priority",
+        config.region_priority
+    );
+    set!(
+        matches,
+        args.language_priority,
+        "language_priority",
+        config.language_priority
+    );
 
-    let mut collection_url: Option<String> = None;
+    args.collection_map = config.collections;
 
-    if !system_name.is_empty() {
-        for collection in collections.clone() {
-            if collection.title.contains(system_name) {
-                matching_collections.push(collection)
-            }
-        }
+    Ok(())
+}
 
-        if matching_collections.len() == 1 {
-            collection_url = Some(matching_collections.get(0).unwrap().url.to_string());
+// transfer settings shared by the commands that download
+fn apply_transfer_config(
+    transfer: &mut TransferArgs,
+    matches: &ArgMatches,
+    config: &config::Settings,
+) -> Result<(), String> {
+    if let Some(jitter) = config.retry_jitter {
+        if !(0.0..=1.0).contains(&jitter) {
+            return Err("Retry jitter in the config must be between 0.0 and 1.0!".to_string());
         }
     }
 
-    if collection_url.is_none() || *select_system {
-        println!(
-            "{}",
-            "Collection for DAT not automatically found, please select from the following:"
-                .yellow()
-        );
-
-        let use_matches = matching_collections.len() > 1 && !*select_system;
-
-        if use_matches {
-            for index in 0..matching_collections.len() {
-                println!(
-                    "{}",
-                    format!(
-                        "{}: {}",
-                        (index + 1).to_string(),
-                        matching_collections.get(index).unwrap().title
-                    )
-                    .yellow()
-                );
-            }
-        } else {
-            for index in 0..collections_len {
-                println!(
-                    "{}",
-                    format!(
-                        "{}: {}",
-                        (index + 1).to_string(),
-                        collections.get(index).unwrap().title
-                    )
-                    .cyan()
-                );
-            }
-        }
-
-        loop {
-            print!("{}", "Input selected collection number: ".cyan());
-            io::stdout().flush().unwrap();
-            let mut collection_choice = String::new();
-            let _ = io::stdin().read_line(&mut collection_choice);
-            let num_test = collection_choice.trim().parse::<usize>();
-
-            match num_test {
-                Ok(num) => {
-                    if use_matches {
-                        if num > 0 && num <= matching_collections.len() {
-                            return matching_collections.get(&num - 1).unwrap().url.to_string();
-                        } else {
-                            println!("{}", "Input number out of range!".red());
-                        }
-                    } else if num > 0 && num <= collections_len {
-                        return collections.get(&num - 1).unwrap().url.to_string();
-                    } else {
-                        println!("{}", "Input number out of range!".red());
-                    }
-                }
-                Err(_) => {
-                    println!("{}", "Invalid number!".red());
-                }
-            }
-        }
-    }
+    set!(matches, transfer.retries, "retries", config.retries);
+    set!(
+        matches,
+        transfer.retry_delay,
+        "retry_delay",
+        config.retry_delay
+    );
+    set!(
+        matches,
+        transfer.retry_max_delay,
+        "retry_max_delay",
+        config.retry_max_delay
+    );
+    set!(
+        matches,
+        transfer.retry_jitter,
+        "retry_jitter",
+        config.retry_jitter
+    );
+    set!(
+        matches,
+        transfer.limit_rate,
+        "limit_rate",
+        config
+            .limit_rate
+            .as_deref()
+            .map(throttle::parse_rate)
+            .transpose()?
+            .map(Some)
+    );
+    set!(
+        matches,
+        transfer.schedule,
+        "schedule",
+        config
+            .schedule
+            .as_ref()
+            .map(|windows| {
+                windows
+                    .iter()
+                    .map(|w| throttle::parse_window(w))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+    );
+    set!(
+        matches,
+        transfer.sanitize,
+        "sanitize",
+        config
+            .sanitize
+            .as_deref()
+            .map(|profile| sanitize::Profile::from_str(profile, true))
+            .transpose()
+            .map_err(|e| format!("Invalid sanitize setting in the config: {}", e))?
+    );
+    set!(
+        matches,
+        transfer.on_collision,
+        "on_collision",
+        config
+            .on_collision
+            .as_deref()
+            .map(|strategy| collision::Strategy::from_str(strategy, true))
+            .transpose()
+            .map_err(|e| format!("Invalid on-collision setting in the config: {}", e))?
+    );
 
-    collection_url.unwrap()
+    Ok(())
 }
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{self, Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::dat::DatRom;
+use crate::sanitize;
+use crate::verify::{self, FileCheck, Hashes, Verification};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// CRC32 checksums
+    Sfv,
+    Md5,
+    Sha1,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Sfv => "sfv",
+            Format::Md5 => "md5",
+            Format::Sha1 => "sha1",
+        }
+    }
+
+    /// Format of a manifest by its extension
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        Format::value_variants()
+            .iter()
+            .find(|format| format.extension() == extension)
+            .copied()
+    }
+
+    /// Hash of the file from the DAT, if the DAT has it
+    pub fn dat_hash(&self, rom: &DatRom) -> Option<String> {
+        match self {
+            Format::Sfv => rom.crc.clone(),
+            Format::Md5 => rom.md5.clone(),
+            Format::Sha1 => rom.sha1.clone(),
+        }
+    }
+
+    pub fn hash(&self, hashes: &Hashes) -> String {
+        match self {
+            Format::Sfv => hashes.crc.clone(),
+            Format::Md5 => hashes.md5.clone(),
+            Format::Sha1 => hashes.sha1.clone(),
+        }
+    }
+}
+
+/// Hashes by file path relative to the manifest, with / as the separator.
+/// SFV lines are "name crc", MD5 and SHA1 lines "hash *name" or "hash  name".
+pub fn parse(text: &str, format: Format) -> BTreeMap<String, String> {
+    let mut entries: BTreeMap<String, String> = BTreeMap::new();
+
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        let entry = match format {
+            Format::Sfv => line
+                .rsplit_once(' ')
+                .map(|(name, hash)| (name.trim_end(), hash)),
+            Format::Md5 | Format::Sha1 => line
+                .split_once(' ')
+                .map(|(hash, name)| (name.strip_prefix(['*', ' ']).unwrap_or(name), hash)),
+        };
+
+        if let Some((name, hash)) = entry {
+            entries.insert(name.replace('\\', "/"), hash.to_lowercase());
+        }
+    }
+
+    entries
+}
+
+fn format_line(format: Format, name: &str, hash: &str) -> String {
+    match format {
+        Format::Sfv => format!("{} {}\n", name, hash.to_uppercase()),
+        Format::Md5 | Format::Sha1 => format!("{} *{}\n", hash, name),
+    }
+}
+
+/// Adds the entries to the manifest in `dir` named after the folder, keeping
+/// the entries of other files already in it
+pub fn write(
+    dir: &Path,
+    format: Format,
+    entries: &BTreeMap<String, String>,
+    profile: sanitize::Profile,
+) -> io::Result<PathBuf> {
+    let folder = path::absolute(dir)?;
+    let name = folder
+        .file_name()
+        .map_or("manifest".into(), |name| name.to_string_lossy());
+    let path = dir.join(sanitize::file_name(
+        &format!("{}.{}", name, format.extension()),
+        profile,
+    ));
+
+    let mut merged = if path.is_file() {
+        parse(&fs::read_to_string(&path)?, format)
+    } else {
+        BTreeMap::new()
+    };
+    merged.extend(entries.clone());
+
+    let mut text = String::new();
+    if format == Format::Sfv {
+        text.push_str("; Generated by auto-myrient\n");
+    }
+    for (name, hash) in merged.iter() {
+        text.push_str(&format_line(format, name, hash));
+    }
+
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+/// Checks the files listed in a manifest against their hashes, relative to `dir`
+pub fn verify(dir: &Path, entries: &BTreeMap<String, String>, format: Format) -> Vec<FileCheck> {
+    entries
+        .iter()
+        .map(|(name, hash)| {
+            let path = dir.join(sanitize::path(name, sanitize::Profile::Posix));
+            if !path.is_file() {
+                return FileCheck {
+                    name: name.clone(),
+                    path: None,
+                    verification: Verification::Failed("not found".to_string()),
+                };
+            }
+
+            let verification = match verify::hash_file(&path) {
+                Ok(hashes) if format.hash(&hashes) == *hash => Verification::Verified,
+                Ok(_) => Verification::Failed("hash mismatch".to_string()),
+                Err(e) => Verification::Failed(e.to_string()),
+            };
+
+            FileCheck {
+                name: name.clone(),
+                path: Some(path),
+                verification,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sfv() {
+        let entries = parse(
+            "; Generated by auto-myrient\nGame (USA).zip 1A2B3C4D\nsub\\Other Game.nes   DEADBEEF\n\n",
+            Format::Sfv,
+        );
+
+        assert_eq!(
+            entries,
+            BTreeMap::from([
+                ("Game (USA).zip".to_string(), "1a2b3c4d".to_string()),
+                ("sub/Other Game.nes".to_string(), "deadbeef".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_binary_and_text_mode_hashes() {
+        let entries = parse(
+            "# comment\nABCDEF *Game (USA).zip\n012345  Other Game.zip\n",
+            Format::Md5,
+        );
+
+        assert_eq!(
+            entries,
+            BTreeMap::from([
+                ("Game (USA).zip".to_string(), "abcdef".to_string()),
+                ("Other Game.zip".to_string(), "012345".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_back_written_lines() {
+        for format in Format::value_variants() {
+            let line = format_line(*format, "Game (USA).zip", "abcdef");
+            let entries = parse(&line, *format);
+
+            assert_eq!(
+                entries.get("Game (USA).zip").map(String::as_str),
+                Some("abcdef")
+            );
+        }
+    }
+
+    #[test]
+    fn finds_formats_by_extension() {
+        assert_eq!(Format::from_path(Path::new("dir/a.SFV")), Some(Format::Sfv));
+        assert_eq!(Format::from_path(Path::new("a.sha1")), Some(Format::Sha1));
+        assert_eq!(Format::from_path(Path::new("a.zip")), None);
+    }
+}
@@ -1,25 +1,32 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::fs::File;
-use std::io::{self, Read};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
 use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
 
 use colored::{Colorize, CustomColor};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use once_cell::sync::Lazy;
-use reqwest::blocking::Client;
-
-use reqwest::header;
-use retry::delay::Exponential;
-use retry::retry;
+use reqwest::blocking::{Client, Response};
+use reqwest::{header, StatusCode};
 use select::document::Document;
-use select::predicate::{Attr, Name, Predicate};
+use select::predicate::{Attr, Class, Name, Predicate};
+use serde::{Deserialize, Serialize};
 
+use crate::cache;
 use crate::constants;
+use crate::interrupt;
+use crate::output::{self, Event, RomStatus};
+use crate::retry::{retry, RetryPolicy, Retryable};
+use crate::say;
+use crate::throttle::{self, Throttle};
 
-const HTTP_CLIENT: Lazy<Client> = Lazy::new(|| Client::new());
+static HTTP_CLIENT: Lazy<Client> = Lazy::new(Client::new);
 
-const MAX_RETRIES: usize = 3;
+const FAILED_RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
 
 const VERB_WIDTH: usize = 11;
 const PROGRESS_PERCENT_PART: &str = "percent:>3";
@@ -35,11 +42,24 @@ pub struct Collection {
     pub title: String,
     pub url: String,
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rom {
     pub name: String,
     pub file: String,
     pub url: String,
+    /// Approximate size from the directory listing
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Path in the output folder from `--layout`, the file name if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl Rom {
+    /// Path of the local file relative to the output folder
+    pub fn local_path(&self) -> &str {
+        self.path.as_deref().unwrap_or(&self.file)
+    }
 }
 struct DownloadProgress<R> {
     inner: R,
@@ -71,48 +91,133 @@ fn build_progress_template(progress_bar: &ProgressBar, position: &Option<f64>) -
     format!(
         "{}{}",
         format!("{{{}}}%", PROGRESS_PERCENT_PART).custom_color(color),
-        format!("{}", PROGESS_TEMPLATE),
+        PROGESS_TEMPLATE,
     )
 }
 
 impl<R: Read> Read for DownloadProgress<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.inner.read(buf).map(|n| {
+        self.inner.read(buf).inspect(|n| {
             self.progress_bar.set_style(
                 ProgressStyle::with_template(
                     build_progress_template(&self.progress_bar, &None).as_str(),
                 )
                 .unwrap(),
             );
-            self.progress_bar.inc(n as u64);
-            n
+            self.progress_bar.inc(*n as u64);
         })
     }
 }
 
-pub fn fetch(path: &String) -> Result<String, reqwest::Error> {
-    let res = HTTP_CLIENT
-        .get(format!("{}{}", constants::MYRIENT_HTTP_ADDR, path))
-        .headers(constants::REQ_HEADERS.clone())
-        .send();
+static BASE_URL: OnceLock<String> = OnceLock::new();
 
-    match res {
-        Ok(res) => match res.text() {
-            Ok(text) => {
-                return Ok(text);
-            }
-            Err(e) => {
-                return Err(e);
-            }
-        },
-        Err(e) => {
-            return Err(e);
+#[derive(Debug)]
+pub enum FetchError {
+    Request(reqwest::Error),
+    /// Listing is not cached and the network is not to be used
+    Offline(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::Request(e) => write!(f, "{}", e),
+            FetchError::Offline(url) => write!(
+                f,
+                "{} is not cached, run once without --offline to cache it",
+                url
+            ),
         }
     }
 }
 
-pub fn get_collections(html: &String) -> Vec<Collection> {
-    let dom = Document::from(html.as_str());
+fn header_value(response: &Response, name: header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)?
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+/// Sets the address of the server, Myrient by default
+pub fn set_base_url(url: &str) {
+    let mut url = url.to_string();
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    let _ = BASE_URL.set(url);
+}
+
+pub fn base_url() -> &'static str {
+    BASE_URL
+        .get()
+        .map_or(constants::MYRIENT_HTTP_ADDR, String::as_str)
+}
+
+/// Fetches a listing, using the cached copy while it is within the TTL and
+/// revalidating it with the server afterwards
+pub fn fetch(path: &str) -> Result<String, FetchError> {
+    let url = format!("{}{}", base_url(), path);
+    let settings = cache::settings();
+    let cached = cache::load_listing(&url);
+
+    if let Some(listing) = &cached {
+        if settings.offline || listing.age() < settings.ttl {
+            return Ok(listing.body.clone());
+        }
+    }
+
+    if settings.offline {
+        return Err(FetchError::Offline(url));
+    }
+
+    let mut request = HTTP_CLIENT
+        .get(&url)
+        .headers(constants::REQ_HEADERS.clone());
+
+    if let Some(listing) = &cached {
+        if let Some(etag) = &listing.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &listing.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().map_err(FetchError::Request)?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(mut listing) = cached {
+            listing.fetched = cache::unix_time();
+            let _ = cache::save_listing(&listing);
+            return Ok(listing.body);
+        }
+    }
+
+    if !response.status().is_success() {
+        return response.text().map_err(FetchError::Request);
+    }
+
+    let etag = header_value(&response, header::ETAG);
+    let last_modified = header_value(&response, header::LAST_MODIFIED);
+
+    let listing = cache::Listing {
+        url: url.clone(),
+        fetched: cache::unix_time(),
+        etag,
+        last_modified,
+        body: response.text().map_err(FetchError::Request)?,
+    };
+
+    // the cache only saves requests, so failing to write it is not an error
+    let _ = cache::save_listing(&listing);
+
+    Ok(listing.body)
+}
+
+pub fn get_collections(html: &str) -> Vec<Collection> {
+    let dom = Document::from(html);
     let mut collections: Vec<Collection> = Vec::new();
 
     let main_dir = dom.find(Attr("id", "list").descendant(Name("tr")));
@@ -140,8 +245,8 @@ pub fn get_collections(html: &String) -> Vec<Collection> {
     collections
 }
 
-pub fn get_catalog_url_by_name(html: &String, catalog_name: &str) -> Option<String> {
-    let dom = Document::from(html.as_str());
+pub fn get_catalog_url_by_name(html: &str, catalog_name: &str) -> Option<String> {
+    let dom = Document::from(html);
     let main_dir = dom.find(Attr("id", "list").descendant(Name("tr")));
     for dir in main_dir {
         let cell_link = dir.find(Name("td").descendant(Name("a"))).next();
@@ -167,8 +272,8 @@ pub fn get_catalog_url_by_name(html: &String, catalog_name: &str) -> Option<Stri
     None
 }
 
-pub fn get_catalogs(html: &String) -> Vec<Catalog> {
-    let dom = Document::from(html.as_str());
+pub fn get_catalogs(html: &str) -> Vec<Catalog> {
+    let dom = Document::from(html);
     let mut catalogs: Vec<Catalog> = Vec::new();
 
     let main_dir = dom.find(Attr("id", "list").descendant(Name("tr")));
@@ -196,8 +301,25 @@ pub fn get_catalogs(html: &String) -> Vec<Catalog> {
     catalogs
 }
 
-pub fn get_roms_for_collection(html: &String) -> HashMap<String, Rom> {
-    let dom = Document::from(html.as_str());
+// parses sizes in the listing such as "1.2 GiB" or "345 B"
+fn parse_listing_size(text: &str) -> Option<u64> {
+    let mut parts = text.split_whitespace();
+    let number: f64 = parts.next()?.parse().ok()?;
+
+    let multiplier: u64 = match parts.next().unwrap_or("B") {
+        "B" => 1,
+        "KiB" | "K" => 1 << 10,
+        "MiB" | "M" => 1 << 20,
+        "GiB" | "G" => 1 << 30,
+        "TiB" | "T" => 1 << 40,
+        _ => return None,
+    };
+
+    Some((number * multiplier as f64) as u64)
+}
+
+pub fn get_roms_for_collection(html: &str) -> HashMap<String, Rom> {
+    let dom = Document::from(html);
 
     let mut roms: HashMap<String, Rom> = HashMap::new();
 
@@ -219,10 +341,16 @@ pub fn get_roms_for_collection(html: &String) -> HashMap<String, Rom> {
                             .unwrap()
                             .to_string_lossy()
                             .to_string();
+                        let size = rom
+                            .find(Name("td").and(Class("size")))
+                            .next()
+                            .and_then(|cell| parse_listing_size(&cell.text()));
                         let rom = Rom {
                             name: name.to_string(),
                             file: title.to_string(),
                             url: href.to_string(),
+                            size,
+                            path: None,
                         };
 
                         roms.insert(name.to_string(), rom);
@@ -235,67 +363,227 @@ pub fn get_roms_for_collection(html: &String) -> HashMap<String, Rom> {
     roms
 }
 
-pub fn download_rom(
-    output_path: &String,
-    rom_url: &String,
-    rom: &Rom,
-    file_index: &usize,
-    total_download_count: &usize,
-) -> Result<File, reqwest::Error> {
-    let local_path = Path::new(output_path).join(&rom.file);
+#[derive(Debug)]
+pub enum DownloadError {
+    /// Request could not be sent or the connection failed
+    Request(reqwest::Error),
+    /// Server responded with an error status
+    Status {
+        status: StatusCode,
+        retry_after: Option<Duration>,
+    },
+    /// Connection dropped mid-download without making any progress
+    Stream(io::Error),
+    /// Local file could not be read or written
+    File(io::Error),
+    /// Run was interrupted, partial file is kept for resuming
+    Interrupted,
+}
 
-    let mut resume_dl = false;
-    let mut proceed_dl = true;
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DownloadError::Request(e) => write!(f, "request failed: {}", e),
+            DownloadError::Status { status, .. } => write!(f, "server responded with {}", status),
+            DownloadError::Stream(e) => write!(f, "connection dropped: {}", e),
+            DownloadError::File(e) => write!(f, "file error: {}", e),
+            DownloadError::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
 
-    let local_file_size = if local_path.exists() {
-        // TODO: handle errors
-        local_path.metadata().unwrap().len() - 1
-    } else {
-        0
-    };
+impl Retryable for DownloadError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::Request(e) => !e.is_builder() && !e.is_redirect(),
+            DownloadError::Status { status, .. } => {
+                status.is_server_error()
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::REQUEST_TIMEOUT
+            }
+            DownloadError::Stream(_) => true,
+            DownloadError::File(_) | DownloadError::Interrupted => false,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            DownloadError::Status { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
 
-    let url = format!("{}{}", constants::MYRIENT_HTTP_ADDR, rom_url);
+fn parse_retry_after(response: &Response) -> Option<Duration> {
+    let status = response.status();
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
 
-    let request = HTTP_CLIENT
-        .get(url.clone())
-        .headers(constants::REQ_HEADERS.clone())
-        .header(header::RANGE, &format!("bytes={}-", local_file_size));
+    let value = response.headers().get(header::RETRY_AFTER)?.to_str().ok()?;
 
-    let response = request.send()?;
+    // either delay in seconds or a HTTP-date
+    match value.trim().parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()
+            .map(|date| date.duration_since(SystemTime::now()).unwrap_or_default()),
+    }
+}
+
+// total size from a "Content-Range: bytes */1234" header
+fn parse_content_range_total(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Fetches the exact size of a remote file
+pub fn fetch_size(rom_url: &str) -> Result<Option<u64>, DownloadError> {
+    let url = format!("{}{}", base_url(), rom_url);
+    let response = HTTP_CLIENT
+        .head(url)
+        .headers(constants::REQ_HEADERS.clone())
+        .send()
+        .map_err(DownloadError::Request)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(DownloadError::Status {
+            status,
+            retry_after: parse_retry_after(&response),
+        });
+    }
 
-    let bytes_left_to_download = response
+    Ok(response
         .headers()
         .get(header::CONTENT_LENGTH)
         .and_then(|ct_len| ct_len.to_str().ok())
-        .and_then(|ct_len| ct_len.parse().ok())
-        .unwrap_or(0);
+        .and_then(|ct_len| ct_len.parse().ok()))
+}
+
+fn request_from(url: &str, offset: u64) -> Result<Response, DownloadError> {
+    let response = HTTP_CLIENT
+        .get(url)
+        .headers(constants::REQ_HEADERS.clone())
+        .header(header::RANGE, &format!("bytes={}-", offset))
+        .send()
+        .map_err(DownloadError::Request)?;
 
-    if bytes_left_to_download == 0 {
-        proceed_dl = false
-    } else if local_file_size > 0 {
-        resume_dl = true
+    let status = response.status();
+    if status.is_success() || status == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(response);
     }
 
+    Err(DownloadError::Status {
+        status,
+        retry_after: parse_retry_after(&response),
+    })
+}
+
+fn copy_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    written: &mut u64,
+    throttle: &Throttle,
+) -> Result<(), DownloadError> {
+    let mut buf = vec![0; COPY_BUFFER_SIZE];
+
+    loop {
+        if interrupt::is_interrupted() {
+            // checkpoint what we have so the download can be resumed
+            writer.flush().map_err(DownloadError::File)?;
+            return Err(DownloadError::Interrupted);
+        }
+
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(DownloadError::Stream(e)),
+        };
+
+        writer.write_all(&buf[..n]).map_err(DownloadError::File)?;
+        *written += n as u64;
+
+        throttle.consume(n);
+    }
+}
+
+pub fn download_rom(
+    output_path: &str,
+    rom_url: &str,
+    rom: &Rom,
+    file_index: &usize,
+    total_download_count: &usize,
+    throttle: &Throttle,
+) -> Result<DownloadStatus, DownloadError> {
+    if interrupt::is_interrupted() {
+        return Err(DownloadError::Interrupted);
+    }
+
+    let local_path = Path::new(output_path).join(rom.local_path());
+
+    let mut local_file_size = match local_path.metadata() {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(DownloadError::File(e)),
+    };
+
+    let url = format!("{}{}", base_url(), rom_url);
     let width = total_download_count.checked_ilog10().unwrap_or(0) as usize + 1;
 
-    if !proceed_dl {
-        // already downloaded, skip
-        println!(
-            "{}",
-            format!(
-                "{:VERB_WIDTH$} {:width$}/{}: {}",
-                "Already DLd", file_index, total_download_count, rom.name
-            )
-            .green()
-        );
+    let mut response = request_from(&url, local_file_size)?;
+
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        let remote_file_size = match parse_content_range_total(&response) {
+            Some(remote_file_size) => Some(remote_file_size),
+            // without the total in the response, ask for the size before trusting the local file
+            None => fetch_size(rom_url)?,
+        };
+
+        match remote_file_size {
+            Some(remote_file_size) if remote_file_size != local_file_size => {
+                // local file does not match the remote one, start over
+                local_file_size = 0;
+                response = request_from(&url, 0)?;
+            }
+            _ => {
+                // already downloaded, skip
+                say!(
+                    "{}",
+                    format!(
+                        "{:VERB_WIDTH$} {:width$}/{}: {}",
+                        "Already DLd", file_index, total_download_count, rom.name
+                    )
+                    .green()
+                );
+
+                return Ok(DownloadStatus::AlreadyDownloaded);
+            }
+        }
+    }
 
-        // TODO: handle errors
-        return Ok(File::open(local_path).unwrap());
+    // server ignoring the range sends the whole file
+    let resume_dl = local_file_size > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+    if !resume_dl {
+        local_file_size = 0;
     }
 
+    let bytes_left_to_download = response.content_length().unwrap_or(0);
     let remote_file_size = bytes_left_to_download + local_file_size;
 
-    let multi_progress = MultiProgress::new();
+    let multi_progress = if output::is_text() {
+        MultiProgress::new()
+    } else {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    };
     let title_bar = multi_progress.add(ProgressBar::new(0));
     let progress_bar = multi_progress.add(ProgressBar::new(remote_file_size));
 
@@ -326,20 +614,62 @@ pub fn download_rom(
         progress_bar,
     };
 
-    // TODO: handle errors
-    let mut writer: std::fs::File = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&local_path)
-        .unwrap();
+    let mut open_options = OpenOptions::new();
+    if resume_dl {
+        open_options.append(true);
+    } else {
+        open_options.write(true).truncate(true);
+    }
+
+    let opened = match local_path.parent() {
+        Some(dir) => fs::create_dir_all(dir),
+        None => Ok(()),
+    }
+    .and_then(|_| open_options.create(true).open(&local_path));
+
+    let mut writer = match opened {
+        Ok(writer) => writer,
+        Err(e) => {
+            reader.progress_bar.finish_and_clear();
+            title_bar.finish_and_clear();
+            return Err(DownloadError::File(e));
+        }
+    };
+
+    let mut downloaded = local_file_size;
+    let result = loop {
+        let offset = downloaded;
+
+        let error = match copy_stream(&mut reader, &mut writer, &mut downloaded, throttle) {
+            Err(DownloadError::Stream(e)) => e,
+            Err(e) => break Err(e),
+            Ok(_) if bytes_left_to_download == 0 || downloaded >= remote_file_size => {
+                break Ok(());
+            }
+            Ok(_) => io::Error::from(io::ErrorKind::UnexpectedEof),
+        };
+
+        if downloaded == offset {
+            // nothing gained since the last connection, let the retry policy decide
+            break Err(DownloadError::Stream(error));
+        }
 
-    // TODO: handle errors
-    io::copy(&mut reader, &mut writer).unwrap();
+        // connection dropped mid-stream, continue from where it left off
+        match request_from(&url, downloaded) {
+            Ok(response) if response.status() == StatusCode::PARTIAL_CONTENT => {
+                reader.inner = response;
+            }
+            Ok(_) => break Err(DownloadError::Stream(error)),
+            Err(e) => break Err(e),
+        }
+    };
 
     reader.progress_bar.finish_and_clear();
     title_bar.finish_and_clear();
 
-    println!(
+    result?;
+
+    say!(
         "{}",
         format!(
             "{:VERB_WIDTH$} {:width$}/{}: {}",
@@ -348,13 +678,36 @@ pub fn download_rom(
         .green()
     );
 
-    Ok(writer)
+    Ok(DownloadStatus::Downloaded)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadStatus {
+    Downloaded,
+    AlreadyDownloaded,
+}
+
+// ROMs that were successfully handled in a bulk download
+#[derive(Debug, Clone, Default)]
+pub struct DownloadSummary {
+    pub downloaded_roms: Vec<Rom>,
+    pub already_downloaded_roms: Vec<Rom>,
+}
+
+impl DownloadSummary {
+    pub fn completed_count(&self) -> usize {
+        self.downloaded_roms.len() + self.already_downloaded_roms.len()
+    }
 }
 
 // custom error type that includes a vector of the failed Rom objects
 #[derive(Debug, Clone)]
 pub struct BulkDownloadError {
+    pub summary: DownloadSummary,
     pub failed_roms: Vec<Rom>,
+    /// ROMs left undownloaded because the run was interrupted
+    pub remaining_roms: Vec<Rom>,
+    pub interrupted: bool,
 }
 
 impl fmt::Display for BulkDownloadError {
@@ -363,47 +716,141 @@ impl fmt::Display for BulkDownloadError {
         for rom in &self.failed_roms {
             write!(f, "{} ", rom.name)?;
         }
+        if self.interrupted {
+            write!(
+                f,
+                "(interrupted with {} ROMs remaining)",
+                self.remaining_roms.len()
+            )?;
+        }
         Ok(())
     }
 }
 
 pub fn download_roms(
-    roms: &Vec<Rom>,
-    output_dir: &String,
-    catalog_url: &String,
-    collection_url: &String,
-) -> Result<(), BulkDownloadError> {
+    roms: &[Rom],
+    output_dir: &str,
+    catalog_url: &str,
+    collection_url: &str,
+    retry_policy: &RetryPolicy,
+    throttle: &Throttle,
+) -> Result<DownloadSummary, BulkDownloadError> {
+    let _transfer = interrupt::Transfer::start();
+
     let mut roms_with_errors: Vec<Rom> = Vec::new();
+    let mut remaining_roms: Vec<Rom> = Vec::new();
+    let mut summary = DownloadSummary::default();
+
+    let total_download_count = roms.len();
+    let width = total_download_count.checked_ilog10().unwrap_or(0) as usize + 1;
+
+    for (index, rom) in roms.iter().enumerate() {
+        let index = index + 1;
 
-    let mut index: usize = 0;
-    for rom in roms {
-        index += 1;
-
-        let download_result = retry(Exponential::from_millis(100).take(MAX_RETRIES), || {
-            download_rom(
-                output_dir,
-                &format!("{}{}{}", catalog_url, collection_url, rom.url),
-                &rom,
-                &index,
-                &roms.len(),
-            )
+        throttle.wait_while_paused(|resume_at| {
+            let until = resume_at.map_or(String::new(), |time| {
+                format!(" until {}", throttle::format_time(time))
+            });
+            say!("{}", format!("Downloads paused{}...", until).yellow());
         });
 
-        if download_result.is_err() {
-            println!("{}", format!("Error with  {}", rom.name).red());
-            roms_with_errors.push(rom.clone());
+        let download_result = retry(
+            retry_policy,
+            || {
+                download_rom(
+                    output_dir,
+                    &format!("{}{}{}", catalog_url, collection_url, rom.url),
+                    rom,
+                    &index,
+                    &total_download_count,
+                    throttle,
+                )
+            },
+            |e, delay| {
+                output::emit(Event::Rom {
+                    name: rom.name.clone(),
+                    status: RomStatus::Retrying,
+                    size: rom.size,
+                    detail: Some(e.to_string()),
+                });
+                say!(
+                    "{}",
+                    format!(
+                        "{:VERB_WIDTH$} {:width$}/{}: {} ({}, waiting {:.1}s)",
+                        "Retrying",
+                        index,
+                        total_download_count,
+                        rom.name,
+                        e,
+                        delay.as_secs_f64()
+                    )
+                    .yellow()
+                );
+            },
+        );
+
+        let rom_event = |status: RomStatus, detail: Option<String>| {
+            output::emit(Event::Rom {
+                name: rom.name.clone(),
+                status,
+                size: rom.size,
+                detail,
+            })
+        };
+
+        match download_result {
+            Ok(DownloadStatus::Downloaded) => {
+                rom_event(RomStatus::Downloaded, None);
+                summary.downloaded_roms.push(rom.clone());
+            }
+            Ok(DownloadStatus::AlreadyDownloaded) => {
+                rom_event(RomStatus::AlreadyDownloaded, None);
+                summary.already_downloaded_roms.push(rom.clone());
+            }
+            Err(DownloadError::Interrupted) => {
+                remaining_roms.extend_from_slice(&roms[index - 1..]);
+                break;
+            }
+            Err(e) => {
+                say!("{}", format!("Error with  {}: {}", rom.name, e).red());
+                rom_event(RomStatus::Failed, Some(e.to_string()));
+                roms_with_errors.push(rom.clone());
+            }
         }
     }
 
-    match roms_with_errors.len() {
-        0 => {
+    let interrupted = interrupt::is_interrupted();
+
+    match roms_with_errors.len() + remaining_roms.len() {
+        0 if !interrupted => {
             // no errors, OK
-            return Ok(());
-        }
-        _ => {
-            return Err(BulkDownloadError {
-                failed_roms: roms_with_errors,
-            });
+            Ok(summary)
         }
+        _ => Err(BulkDownloadError {
+            summary,
+            failed_roms: roms_with_errors,
+            remaining_roms,
+            interrupted,
+        }),
     }
 }
+
+/// Downloads previously failed ROMs, backing off for longer between retries
+/// since these already failed once.
+pub fn retry_failed_roms(
+    roms: &[Rom],
+    output_dir: &str,
+    catalog_url: &str,
+    collection_url: &str,
+    retry_policy: &RetryPolicy,
+    throttle: &Throttle,
+) -> Result<DownloadSummary, BulkDownloadError> {
+    download_roms(
+        roms,
+        output_dir,
+        catalog_url,
+        collection_url,
+        &retry_policy.with_min_base_delay(FAILED_RETRY_BASE_DELAY),
+        throttle,
+    )
+}
@@ -0,0 +1,278 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use crate::dat::Game;
+use crate::tags::{self, NameTags};
+
+#[derive(Debug, Clone)]
+pub struct Priorities {
+    pub regions: Vec<String>,
+    pub languages: Vec<String>,
+}
+
+// Which game was kept for a parent, and why
+#[derive(Debug, Clone)]
+pub struct Selection {
+    pub parent: String,
+    pub chosen: String,
+    pub candidates: usize,
+    pub reason: String,
+}
+
+// Lower is better, compared field by field in order of importance
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Score {
+    prerelease: bool,
+    region_rank: usize,
+    language_rank: usize,
+    revision: Reverse<Vec<(usize, String)>>,
+    version: Reverse<Vec<(usize, String)>>,
+    clone: bool,
+    flag_count: usize,
+}
+
+struct Candidate {
+    name: String,
+    tags: NameTags,
+    score: Score,
+}
+
+// orders "2" < "10" and "A" < "B" by comparing lengths first
+fn version_key(version: &Option<String>) -> Vec<(usize, String)> {
+    match version {
+        None => Vec::new(),
+        Some(version) => version
+            .split('.')
+            .map(|part| (part.len(), part.to_string()))
+            .collect(),
+    }
+}
+
+fn rank(values: &[String], priorities: &[String]) -> usize {
+    values
+        .iter()
+        .filter_map(|value| {
+            priorities
+                .iter()
+                .position(|priority| priority.eq_ignore_ascii_case(value))
+        })
+        .min()
+        .unwrap_or(priorities.len())
+}
+
+fn best_of(values: &[String], priorities: &[String]) -> String {
+    values
+        .iter()
+        .min_by_key(|value| rank(std::slice::from_ref(value), priorities))
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn score(tags: &NameTags, clone: bool, priorities: &Priorities) -> Score {
+    Score {
+        prerelease: tags.is_prerelease(),
+        region_rank: rank(&tags.regions, &priorities.regions),
+        language_rank: rank(&tags.languages, &priorities.languages),
+        revision: Reverse(version_key(&tags.revision)),
+        version: Reverse(version_key(&tags.version)),
+        clone,
+        flag_count: tags.flags.len(),
+    }
+}
+
+// explains the first criterion where the chosen candidate beat the runner-up
+fn reason(chosen: &Candidate, runner_up: Option<&Candidate>, priorities: &Priorities) -> String {
+    let runner_up = match runner_up {
+        None => return "only candidate".to_string(),
+        Some(runner_up) => runner_up,
+    };
+
+    let (a, b) = (&chosen.score, &runner_up.score);
+
+    if a.prerelease != b.prerelease {
+        "not a pre-release".to_string()
+    } else if a.region_rank != b.region_rank {
+        format!(
+            "region {} preferred",
+            best_of(&chosen.tags.regions, &priorities.regions)
+        )
+    } else if a.language_rank != b.language_rank {
+        format!(
+            "language {} preferred",
+            best_of(&chosen.tags.languages, &priorities.languages)
+        )
+    } else if a.revision != b.revision {
+        format!(
+            "latest revision (Rev {})",
+            chosen.tags.revision.clone().unwrap_or_default()
+        )
+    } else if a.version != b.version {
+        format!(
+            "latest version (v{})",
+            chosen.tags.version.clone().unwrap_or_default()
+        )
+    } else if a.clone != b.clone {
+        "parent preferred".to_string()
+    } else if a.flag_count != b.flag_count {
+        "fewest extra tags".to_string()
+    } else {
+        "first listed".to_string()
+    }
+}
+
+/// Keeps one game per parent. Parents come from `cloneof` when the DAT has
+/// them, otherwise games with the same title are grouped together. All discs
+/// of the chosen game are kept.
+pub fn select(games: Vec<Game>, priorities: &Priorities) -> (Vec<Game>, Vec<Selection>) {
+    let has_clone_info = games.iter().any(|game| game.cloneof.is_some());
+
+    // parent -> disc-less variants, in DAT order
+    let mut groups: Vec<(String, Vec<(String, bool)>)> = Vec::new();
+    let mut group_index: HashMap<String, usize> = HashMap::new();
+
+    for game in games.iter() {
+        let variant = tags::without_disc(&game.name);
+        let parent = if has_clone_info {
+            tags::without_disc(game.cloneof.as_ref().unwrap_or(&game.name))
+        } else {
+            tags::parse(&game.name).title
+        };
+
+        let index = *group_index.entry(parent.clone()).or_insert_with(|| {
+            groups.push((parent, Vec::new()));
+            groups.len() - 1
+        });
+
+        let variants = &mut groups[index].1;
+        if !variants.iter().any(|(name, _)| *name == variant) {
+            variants.push((variant, game.cloneof.is_some()));
+        }
+    }
+
+    let mut chosen_variants: HashSet<String> = HashSet::new();
+    let mut selections: Vec<Selection> = Vec::new();
+
+    for (parent, variants) in groups {
+        let mut candidates: Vec<Candidate> = variants
+            .into_iter()
+            .map(|(name, clone)| {
+                let tags = tags::parse(&name);
+                let score = score(&tags, clone, priorities);
+                Candidate { name, tags, score }
+            })
+            .collect();
+
+        // stable sort keeps DAT order for equal scores
+        candidates.sort_by(|a, b| a.score.cmp(&b.score));
+
+        let reason = reason(&candidates[0], candidates.get(1), priorities);
+        chosen_variants.insert(candidates[0].name.clone());
+
+        selections.push(Selection {
+            parent,
+            chosen: candidates[0].name.clone(),
+            candidates: candidates.len(),
+            reason,
+        });
+    }
+
+    let games = games
+        .into_iter()
+        .filter(|game| chosen_variants.contains(&tags::without_disc(&game.name)))
+        .collect();
+
+    (games, selections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(name: &str, cloneof: Option<&str>) -> Game {
+        Game {
+            name: name.to_string(),
+            description: None,
+            category: None,
+            cloneof: cloneof.map(String::from),
+            roms: Vec::new(),
+        }
+    }
+
+    fn priorities() -> Priorities {
+        Priorities {
+            regions: vec!["Europe".to_string(), "USA".to_string()],
+            languages: vec!["En".to_string()],
+        }
+    }
+
+    fn chosen(games: Vec<Game>) -> Vec<String> {
+        let (games, _) = select(games, &priorities());
+        games.into_iter().map(|game| game.name).collect()
+    }
+
+    #[test]
+    fn prefers_regions_in_order() {
+        let games = vec![
+            game("Game (USA)", None),
+            game("Game (Europe)", None),
+            game("Game (Japan)", None),
+        ];
+
+        assert_eq!(chosen(games), ["Game (Europe)"]);
+    }
+
+    #[test]
+    fn prefers_releases_over_prereleases() {
+        let games = vec![game("Game (Europe) (Beta)", None), game("Game (USA)", None)];
+
+        assert_eq!(chosen(games), ["Game (USA)"]);
+    }
+
+    #[test]
+    fn prefers_the_latest_revision() {
+        let games = vec![
+            game("Game (Europe) (Rev 2)", None),
+            game("Game (Europe) (Rev 10)", None),
+            game("Game (Europe)", None),
+        ];
+
+        assert_eq!(chosen(games), ["Game (Europe) (Rev 10)"]);
+    }
+
+    #[test]
+    fn groups_by_parent_and_keeps_all_discs() {
+        let games = vec![
+            game("Game (USA) (Disc 1)", None),
+            game("Game (USA) (Disc 2)", None),
+            game("Other Name (Europe) (Disc 1)", Some("Game (USA) (Disc 1)")),
+            game("Other Name (Europe) (Disc 2)", Some("Game (USA) (Disc 2)")),
+        ];
+
+        let (games, selections) = select(games, &priorities());
+        let names: Vec<&str> = games.iter().map(|game| game.name.as_str()).collect();
+
+        assert_eq!(
+            names,
+            [
+                "Other Name (Europe) (Disc 1)",
+                "Other Name (Europe) (Disc 2)"
+            ]
+        );
+        assert_eq!(selections.len(), 1);
+        assert_eq!(selections[0].candidates, 2);
+        assert_eq!(selections[0].reason, "region Europe preferred");
+    }
+
+    #[test]
+    fn keeps_dat_order_for_equal_scores() {
+        let games = vec![
+            game("Game (Europe) (A)", None),
+            game("Game (Europe) (B)", None),
+        ];
+
+        let (_, selections) = select(games, &priorities());
+
+        assert_eq!(selections[0].chosen, "Game (Europe) (A)");
+        assert_eq!(selections[0].reason, "first listed");
+    }
+}
@@ -0,0 +1,188 @@
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Colored text with progress bars
+    Text,
+    /// A single JSON array of all events, printed when the run ends
+    Json,
+    /// One JSON event per line as they happen
+    Jsonl,
+}
+
+static FORMAT: OnceLock<Format> = OnceLock::new();
+
+// events waiting to be printed as a single JSON document
+static EVENTS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RomStatus {
+    Planned,
+    Downloaded,
+    AlreadyDownloaded,
+    Retrying,
+    Failed,
+    Missing,
+    Verified,
+    VerificationFailed,
+    Unverified,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Catalog and collection the run downloads from
+    Resolved {
+        catalog_url: String,
+        collection_url: String,
+    },
+    /// Catalog, collection or file on the server
+    Listed {
+        name: String,
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+    },
+    /// Search result
+    Found {
+        name: String,
+        catalog: String,
+        collection: String,
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+    },
+    Counts {
+        wanted: usize,
+        found: usize,
+        missing: usize,
+    },
+    Rom {
+        name: String,
+        status: RomStatus,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    /// File whose local path is used by another file, saved to `resolved` or skipped
+    Collision {
+        name: String,
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        resolved: Option<String>,
+    },
+    /// File found by prune, with what was done to it: listed, quarantined or deleted
+    Pruned {
+        path: String,
+        reason: String,
+        action: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    /// Local file renamed to a wanted ROM matching it by size and hashes
+    Rebuilt {
+        name: String,
+        from: String,
+        to: String,
+    },
+    /// Game that differs between two DAT-files: added, removed, renamed or rehashed
+    Changed {
+        change: String,
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        old_name: Option<String>,
+    },
+    /// DAT-file the output was last completely downloaded from
+    Synced {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        date: Option<String>,
+    },
+    /// File saved under another path than its name on the server
+    Renamed { path: String, local_path: String },
+    /// File in the quarantine folder, relative to it
+    Quarantined { path: String },
+    Finished {
+        downloaded: usize,
+        already_downloaded: usize,
+        failed: usize,
+        remaining: usize,
+        interrupted: bool,
+    },
+}
+
+/// Sets the output format. Anything but text turns off colors, and human
+/// readable output moves to stderr so stdout only has JSON.
+pub fn set_format(format: Format) {
+    let _ = FORMAT.set(format);
+
+    if format != Format::Text {
+        colored::control::set_override(false);
+    }
+}
+
+pub fn is_text() -> bool {
+    FORMAT.get().is_none_or(|format| *format == Format::Text)
+}
+
+/// Prints a line of human readable output, to stderr when printing JSON
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::is_text() {
+            println!($($arg)*)
+        } else {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+/// Prints a question for the user without a newline
+pub fn prompt(text: &str) {
+    if is_text() {
+        print!("{}", text);
+        let _ = io::stdout().flush();
+    } else {
+        eprint!("{}", text);
+        let _ = io::stderr().flush();
+    }
+}
+
+pub fn emit(event: Event) {
+    let value = match serde_json::to_value(&event) {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+
+    match FORMAT.get() {
+        Some(Format::Jsonl) => println!("{}", value),
+        Some(Format::Json) => EVENTS.lock().unwrap().push(value),
+        _ => {}
+    }
+}
+
+/// Prints the collected events when printing a single JSON document
+pub fn finish() {
+    if FORMAT.get() != Some(&Format::Json) {
+        return;
+    }
+
+    let events = std::mem::take(&mut *EVENTS.lock().unwrap());
+    if let Ok(json) = serde_json::to_string_pretty(&events) {
+        println!("{}", json);
+    }
+}
+
+/// Exits with `code` after printing any collected events
+pub fn exit(code: i32) -> ! {
+    finish();
+    std::process::exit(code);
+}
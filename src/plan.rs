@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use colored::Colorize;
+use indicatif::DecimalBytes;
+
+use crate::myrient::{self, Rom};
+use crate::say;
+
+const STATUS_WIDTH: usize = 10;
+
+// Server files that pack the ROMs, so their size is not the one in the DAT
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "7z", "rar"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Not downloaded yet
+    New,
+    /// Partially downloaded, continues from the local file
+    Resume,
+    /// Already downloaded
+    Complete,
+    /// Local file is larger than the remote one and is downloaded again
+    Mismatched,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Status::New => "New",
+            Status::Resume => "Resume",
+            Status::Complete => "Complete",
+            Status::Mismatched => "Mismatched",
+        };
+        f.pad(text)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub rom: Rom,
+    pub status: Status,
+    pub local_size: u64,
+    /// Size of the remote file, if known
+    pub remote_size: Option<u64>,
+}
+
+impl Entry {
+    /// Bytes that have to be transferred for this file
+    pub fn transfer_size(&self) -> Option<u64> {
+        match self.status {
+            Status::Complete => Some(0),
+            Status::Resume => self
+                .remote_size
+                .map(|size| size.saturating_sub(self.local_size)),
+            Status::New | Status::Mismatched => self.remote_size,
+        }
+    }
+
+    /// Bytes the output filesystem grows by after this file is downloaded
+    pub fn required_space(&self) -> Option<u64> {
+        self.remote_size
+            .map(|size| size.saturating_sub(self.local_size))
+    }
+}
+
+pub struct Plan {
+    pub entries: Vec<Entry>,
+}
+
+impl Plan {
+    pub fn total_transfer_size(&self) -> u64 {
+        self.entries.iter().filter_map(Entry::transfer_size).sum()
+    }
+
+    pub fn total_required_space(&self) -> u64 {
+        self.entries.iter().filter_map(Entry::required_space).sum()
+    }
+
+    /// Amount of files whose size could not be determined
+    pub fn unknown_size_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.remote_size.is_none())
+            .count()
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    }
+}
+
+fn is_archive(file: &str) -> bool {
+    Path::new(file)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| ARCHIVE_EXTENSIONS.contains(&extension.as_str()))
+}
+
+/// Builds the download plan for `roms`. Sizes come from the DAT when the
+/// server file is the ROM itself rather than an archive of it, otherwise
+/// from the listing. With `exact`, local files are compared against the size
+/// reported by the server.
+pub fn build(
+    roms: &[Rom],
+    output_dir: &str,
+    url_prefix: &str,
+    dat_sizes: &HashMap<String, u64>,
+    exact: bool,
+) -> Plan {
+    let mut entries: Vec<Entry> = Vec::new();
+
+    for rom in roms {
+        let local_path = Path::new(output_dir).join(rom.local_path());
+        let local_size = local_path.metadata().map_or(0, |metadata| metadata.len());
+        let local_exists = local_path.is_file();
+
+        let dat_size = if is_archive(&rom.file) {
+            None
+        } else {
+            dat_sizes.get(&rom.file).copied()
+        };
+        let mut remote_size = dat_size.or(rom.size);
+        let mut exact_size = dat_size.is_some();
+
+        if exact && local_exists && !exact_size {
+            if let Ok(Some(size)) = myrient::fetch_size(&format!("{}{}", url_prefix, rom.url)) {
+                remote_size = Some(size);
+                exact_size = true;
+            }
+        }
+
+        let status = match remote_size {
+            _ if !local_exists => Status::New,
+            None => Status::Resume,
+            Some(size) if local_size == size => Status::Complete,
+            // listing sizes are rounded, so only exact sizes can tell a complete file
+            Some(size) if local_size < size || !exact_size => Status::Resume,
+            Some(_) => Status::Mismatched,
+        };
+
+        entries.push(Entry {
+            rom: rom.clone(),
+            status,
+            local_size,
+            remote_size,
+        });
+    }
+
+    Plan { entries }
+}
+
+pub fn print_entries(plan: &Plan) {
+    for entry in plan.entries.iter() {
+        let size = match entry.transfer_size() {
+            Some(size) => DecimalBytes(size).to_string(),
+            None => "unknown".to_string(),
+        };
+        let line = format!(
+            "{:STATUS_WIDTH$} {:>10}: {}",
+            entry.status,
+            size,
+            entry.rom.local_path()
+        );
+
+        match entry.status {
+            Status::New => say!("{}", line.cyan()),
+            Status::Resume => say!("{}", line.yellow()),
+            Status::Complete => say!("{}", line.green()),
+            Status::Mismatched => say!("{}", line.red()),
+        }
+    }
+}
+
+pub fn print_summary(plan: &Plan) {
+    say!(
+        "{}",
+        format!(
+            "Files new / resume / complete / mismatched: {} / {} / {} / {}",
+            plan.count(Status::New),
+            plan.count(Status::Resume),
+            plan.count(Status::Complete),
+            plan.count(Status::Mismatched),
+        )
+        .green()
+    );
+    say!(
+        "{}",
+        format!(
+            "Total to transfer                         : {}",
+            DecimalBytes(plan.total_transfer_size())
+        )
+        .green()
+    );
+
+    let unknown = plan.unknown_size_count();
+    if unknown > 0 {
+        say!(
+            "{}",
+            format!("Files with unknown size                   : {}", unknown).yellow()
+        );
+    }
+}
+
+/// Checks that the planned downloads fit on the output filesystem
+pub fn check_free_space(plan: &Plan, output_dir: &str) -> Result<(), String> {
+    let free_space = fs2::available_space(output_dir)
+        .map_err(|e| format!("Error checking free space: {}", e))?;
+    let required_space = plan.total_required_space();
+
+    say!(
+        "{}",
+        format!(
+            "Free space at output                      : {}",
+            DecimalBytes(free_space)
+        )
+        .green()
+    );
+
+    if required_space > free_space {
+        return Err(format!(
+            "Not enough free space at output, {} required but only {} available!",
+            DecimalBytes(required_space),
+            DecimalBytes(free_space)
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{self, Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::dat::{self, Game};
+use crate::export;
+use crate::manifest;
+use crate::report;
+use crate::sanitize;
+use crate::tags;
+use crate::verify::{self, Verification};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// Not matched by any entry of the DAT
+    NotInDat,
+    /// Matched by a DAT entry, but with the wrong size or hashes
+    FailedVerification,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Category::NotInDat => "Not in DAT",
+            Category::FailedVerification => "Failed",
+        };
+        f.pad(text)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub category: Category,
+    /// Why verification failed
+    pub detail: Option<String>,
+}
+
+// the name sanitized with each profile, as the one of the download is not known
+fn sanitized_names(name: &str) -> impl Iterator<Item = String> + '_ {
+    sanitize::Profile::value_variants()
+        .iter()
+        .map(move |profile| sanitize::file_name(name, *profile))
+}
+
+/// Files auto-myrient writes next to the ROMs, which are never pruned
+pub struct Generated {
+    names: HashSet<String>,
+}
+
+impl Generated {
+    /// Reports, have/miss DATs, exports and .m3u playlists of the system's
+    /// multi-disc games
+    pub fn new(system: &str, games: &[Game]) -> Generated {
+        let base_name = report::base_name(system);
+
+        let mut names: Vec<String> = vec![
+            export::GAMELIST_FILE.to_string(),
+            format!("{}.lpl", base_name),
+            format!("{} (have).dat", base_name),
+            format!("{} (miss).dat", base_name),
+        ];
+        names.extend(
+            report::Format::value_variants()
+                .iter()
+                .map(|format| format!("{} report.{}", base_name, format.extension())),
+        );
+        names.extend(
+            games
+                .iter()
+                .flat_map(|game| {
+                    game.roms
+                        .iter()
+                        .map(|rom| dat::file_stem(&rom.name))
+                        .chain([game.name.clone()])
+                })
+                .filter(|name| tags::parse(name).disc.is_some())
+                .map(|name| format!("{}.m3u", tags::without_disc(&name))),
+        );
+
+        Generated {
+            names: names
+                .iter()
+                .flat_map(|name| sanitized_names(name))
+                .collect(),
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => return false,
+        };
+        if self.names.contains(name.as_ref()) {
+            return true;
+        }
+
+        // checksum manifests are named after their folder
+        let format = match manifest::Format::from_path(path) {
+            Some(format) => format,
+            None => return false,
+        };
+        let folder = path::absolute(path)
+            .ok()
+            .and_then(|path| Some(path.parent()?.file_name()?.to_string_lossy().to_string()))
+            .unwrap_or("manifest".to_string());
+        sanitized_names(&format!("{}.{}", folder, format.extension()))
+            .any(|manifest_name| manifest_name == name)
+    }
+}
+
+/// Finds the files in `dir` not matched by any DAT entry, and with `verify`
+/// also the matched ones failing verification. Files written by auto-myrient
+/// for `system` are left out.
+pub fn find(
+    dir: &Path,
+    system: &str,
+    games: &[Game],
+    names: &HashMap<String, String>,
+    verify: bool,
+) -> io::Result<Vec<Candidate>> {
+    let generated = Generated::new(system, games);
+    let wanted: HashSet<String> = dat::get_wanted_roms(games).into_iter().collect();
+    let roms_by_stem = dat::get_roms_by_stem(games);
+
+    let matched: HashMap<PathBuf, (String, String)> = verify::find_local_files(dir, names)?
+        .into_iter()
+        .filter(|(stem, _)| wanted.contains(stem))
+        .map(|(stem, (file_name, path))| (path, (stem, file_name)))
+        .collect();
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for path in verify::list_files(dir)? {
+        let (stem, file_name) = match matched.get(&path) {
+            Some(found) => found,
+            None => {
+                if !generated.contains(&path) {
+                    candidates.push(Candidate {
+                        path,
+                        category: Category::NotInDat,
+                        detail: None,
+                    });
+                }
+                continue;
+            }
+        };
+
+        if verify {
+            let dat_roms = roms_by_stem
+                .get(stem)
+                .map_or(&[][..], |roms| roms.as_slice());
+            if let Verification::Failed(reason) = verify::verify_file(&path, file_name, dat_roms) {
+                candidates.push(Candidate {
+                    path,
+                    category: Category::FailedVerification,
+                    detail: Some(reason),
+                });
+            }
+        }
+    }
+
+    candidates.sort_by(|a, b| (a.category as u8, &a.path).cmp(&(b.category as u8, &b.path)));
+
+    Ok(candidates)
+}
+
+// "name.ext", then "name (2).ext" and so on for the first path not taken
+fn free_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut target = path.to_path_buf();
+    let mut number = 2;
+    while target.exists() {
+        target.set_file_name(format!("{} ({}){}", stem, number, extension));
+        number += 1;
+    }
+    target
+}
+
+/// Moves a file of `dir` into the quarantine folder, keeping its path under
+/// `dir` so it can be put back. A file quarantined before under the same
+/// path is kept, and the new one numbered.
+pub fn quarantine(dir: &Path, path: &Path, quarantine_dir: &Path) -> io::Result<PathBuf> {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    let target = free_path(&quarantine_dir.join(relative));
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // fall back to copying when the quarantine is on another filesystem
+    if fs::rename(path, &target).is_err() {
+        fs::copy(path, &target)?;
+        fs::remove_file(path)?;
+    }
+
+    Ok(target)
+}
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use zip::ZipArchive;
+
+use crate::dat::{self, DatRom, Game};
+use crate::myrient::Rom;
+use crate::prune;
+use crate::verify;
+
+// A local file with the contents of a wanted ROM under another name
+#[derive(Debug, Clone)]
+pub struct Rename {
+    pub rom: Rom,
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+// sizes and CRCs of the files in a zip, in order
+type ZipKey = Vec<(u64, String)>;
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase()
+}
+
+fn zip_key(roms: &[DatRom]) -> Option<ZipKey> {
+    let mut key: ZipKey = roms
+        .iter()
+        .map(|rom| Some((rom.size?, rom.crc.clone()?)))
+        .collect::<Option<_>>()?;
+    key.sort();
+    Some(key)
+}
+
+// reads the sizes and CRCs stored in the zip, without extracting
+fn read_zip_key(path: &Path) -> io::Result<ZipKey> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+
+    let mut key: ZipKey = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index)?;
+        if entry.is_file() {
+            key.push((entry.size(), format!("{:08x}", entry.crc32())));
+        }
+    }
+    key.sort();
+    Ok(key)
+}
+
+/// Finds local files not matching any DAT entry by name whose size and
+/// CRC/SHA1 match a wanted ROM missing from `dir`. Zips are matched by the
+/// sizes and CRCs of their contents. Files written by auto-myrient for
+/// `system` are left alone.
+pub fn find(
+    dir: &Path,
+    system: &str,
+    roms: &[Rom],
+    games: &[Game],
+    names: &HashMap<String, String>,
+) -> io::Result<Vec<Rename>> {
+    let roms_by_stem = dat::get_roms_by_stem(games);
+
+    let mut zips: Vec<(ZipKey, &Rom)> = Vec::new();
+    let mut files: Vec<(&DatRom, &Rom)> = Vec::new();
+    for rom in roms
+        .iter()
+        .filter(|rom| !dir.join(rom.local_path()).exists())
+    {
+        let dat_roms = match roms_by_stem.get(&rom.name) {
+            Some(dat_roms) => dat_roms,
+            None => continue,
+        };
+
+        if verify::is_zip(Path::new(&rom.file)) {
+            if let Some(key) = zip_key(dat_roms) {
+                zips.push((key, rom));
+            }
+        } else if let Some(dat_rom) = dat_roms.iter().find(|dat_rom| {
+            dat_rom.name == rom.file
+                && dat_rom.size.is_some()
+                && (dat_rom.crc.is_some() || dat_rom.sha1.is_some())
+        }) {
+            files.push((dat_rom, rom));
+        }
+    }
+
+    if zips.is_empty() && files.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // files matching a DAT entry by name are left alone
+    let wanted: HashSet<String> = dat::get_wanted_roms(games).into_iter().collect();
+    let matched: HashSet<PathBuf> = verify::find_local_files(dir, names)?
+        .into_iter()
+        .filter(|(stem, _)| wanted.contains(stem))
+        .map(|(_, (_, path))| path)
+        .collect();
+
+    let generated = prune::Generated::new(system, games);
+    let mut renames: Vec<Rename> = Vec::new();
+
+    for path in verify::list_files(dir)? {
+        if matched.contains(&path) || generated.contains(&path) {
+            continue;
+        }
+
+        let found = if verify::is_zip(&path) {
+            let key = match read_zip_key(&path) {
+                Ok(key) => key,
+                Err(_) => continue,
+            };
+            zips.iter()
+                .position(|(zip_key, _)| *zip_key == key)
+                .map(|index| zips.remove(index).1)
+        } else {
+            let size = match path.metadata() {
+                Ok(metadata) => metadata.len(),
+                Err(_) => continue,
+            };
+            let local_extension = extension(&path);
+            let candidates = |(dat_rom, rom): &(&DatRom, &Rom)| {
+                dat_rom.size == Some(size) && extension(Path::new(&rom.file)) == local_extension
+            };
+            if !files.iter().any(candidates) {
+                continue;
+            }
+
+            // only hashed when a missing ROM has the same size
+            let hashes = match verify::hash_file(&path) {
+                Ok(hashes) => hashes,
+                Err(_) => continue,
+            };
+            files
+                .iter()
+                .position(|file| candidates(file) && verify::matches_rom(&hashes, file.0))
+                .map(|index| files.remove(index).1)
+        };
+
+        if let Some(rom) = found {
+            renames.push(Rename {
+                rom: rom.clone(),
+                from: path,
+                to: dir.join(rom.local_path()),
+            });
+        }
+    }
+
+    Ok(renames)
+}
+
+/// Moves a found file to the path of its ROM
+pub fn rename(rename: &Rename) -> io::Result<()> {
+    if let Some(parent) = rename.to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&rename.from, &rename.to)
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[value(name = "md")]
+    Markdown,
+    Html,
+    Csv,
+}
+
+impl Format {
+    pub fn extension(&self) -> &str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Downloaded,
+    AlreadyDownloaded,
+    Verified,
+    VerificationFailed,
+    MissingAtServer,
+    Failed,
+}
+
+impl Status {
+    const ALL: [Status; 6] = [
+        Status::Downloaded,
+        Status::AlreadyDownloaded,
+        Status::Verified,
+        Status::VerificationFailed,
+        Status::MissingAtServer,
+        Status::Failed,
+    ];
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Status::Downloaded => "Downloaded",
+            Status::AlreadyDownloaded => "Already downloaded",
+            Status::Verified => "Verified",
+            Status::VerificationFailed => "Verification failed",
+            Status::MissingAtServer => "Missing at server",
+            Status::Failed => "Failed",
+        };
+        f.pad(text)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+}
+
+pub struct Report {
+    pub system: String,
+    /// Amount of wanted ROMs found at the server
+    pub found_count: usize,
+    pub items: Vec<Item>,
+}
+
+impl Report {
+    pub fn count(&self, status: Status) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.status == status)
+            .count()
+    }
+
+    fn summary_rows(&self) -> Vec<(String, usize)> {
+        let mut rows = vec![("Found at server".to_string(), self.found_count)];
+        for status in Status::ALL {
+            rows.push((status.to_string(), self.count(status)));
+        }
+        rows
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_csv(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn to_markdown(report: &Report) -> String {
+    let mut md = format!("# {}\n\n", report.system);
+
+    md.push_str("| Status | Amount |\n| --- | ---: |\n");
+    for (status, count) in report.summary_rows() {
+        md.push_str(&format!("| {} | {} |\n", status, count));
+    }
+
+    md.push_str("\n| Name | Status | Detail |\n| --- | --- | --- |\n");
+    for item in report.items.iter() {
+        md.push_str(&format!(
+            "| {} | {} | {} |\n",
+            escape_markdown(&item.name),
+            item.status,
+            escape_markdown(&item.detail)
+        ));
+    }
+
+    md
+}
+
+fn to_html(report: &Report) -> String {
+    let system = escape_html(&report.system);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n",
+        system, system
+    );
+
+    html.push_str("<table>\n<tr><th>Status</th><th>Amount</th></tr>\n");
+    for (status, count) in report.summary_rows() {
+        html.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", status, count));
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<table>\n<tr><th>Name</th><th>Status</th><th>Detail</th></tr>\n");
+    for item in report.items.iter() {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&item.name),
+            item.status,
+            escape_html(&item.detail)
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    html
+}
+
+fn to_csv(report: &Report) -> String {
+    let mut csv = String::from("system,name,status,detail\n");
+
+    for item in report.items.iter() {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            escape_csv(&report.system),
+            escape_csv(&item.name),
+            escape_csv(&item.status.to_string()),
+            escape_csv(&item.detail)
+        ));
+    }
+
+    csv
+}
+
+/// Name the reports, have/miss DATs and playlist of a system start with.
+/// System names are used in file names, so they are kept to a single path
+/// component.
+pub fn base_name(system: &str) -> String {
+    if system.is_empty() {
+        "auto-myrient".to_string()
+    } else {
+        system.replace(['/', '\\'], "-")
+    }
+}
+
+/// Writes the report in each format as "<base name> report.<extension>"
+pub fn write(
+    report: &Report,
+    dir: &Path,
+    base_name: &str,
+    formats: &[Format],
+) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+
+    for format in formats {
+        let path = dir.join(format!("{} report.{}", base_name, format.extension()));
+        let contents = match format {
+            Format::Markdown => to_markdown(report),
+            Format::Html => to_html(report),
+            Format::Csv => to_csv(report),
+        };
+
+        fs::write(&path, contents)?;
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::interrupt;
+
+// Errors that know whether trying again could help
+pub trait Retryable {
+    fn is_retryable(&self) -> bool;
+
+    /// Delay requested by the server before trying again, if any
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Amount of retries after the first attempt
+    pub retries: usize,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction (0.0 - 1.0) of each delay that is randomized
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Same policy, but waiting at least `base_delay` before the first retry
+    pub fn with_min_base_delay(&self, base_delay: Duration) -> RetryPolicy {
+        RetryPolicy {
+            base_delay: self.base_delay.max(base_delay),
+            max_delay: self.max_delay.max(base_delay),
+            ..self.clone()
+        }
+    }
+
+    /// Exponential backoff for the given retry (0-based), capped to `max_delay`
+    pub fn delay(&self, retry: usize) -> Duration {
+        let factor = 2u32.saturating_pow(retry.min(31) as u32);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return delay;
+        }
+
+        let scale = 1.0 - jitter * rand::thread_rng().gen::<f64>();
+        delay.mul_f64(scale)
+    }
+}
+
+/// Runs `operation` until it succeeds, fails with a non-retryable error or
+/// runs out of retries. `on_retry` is called before waiting for each retry.
+pub fn retry<T, E, O, F>(policy: &RetryPolicy, mut operation: O, mut on_retry: F) -> Result<T, E>
+where
+    E: Retryable,
+    O: FnMut() -> Result<T, E>,
+    F: FnMut(&E, Duration),
+{
+    let mut retry = 0;
+
+    loop {
+        match operation() {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if !e.is_retryable() || retry >= policy.retries {
+                    return Err(e);
+                }
+
+                // the server's delay is capped too, so a long Retry-After does not stall the run
+                let delay = e
+                    .retry_after()
+                    .map(|delay| delay.min(policy.max_delay))
+                    .unwrap_or_else(|| policy.delay(retry));
+                on_retry(&e, delay);
+                interrupt::sleep(delay);
+
+                retry += 1;
+            }
+        }
+    }
+}
+
+/// Parses a jitter fraction, which has to be between 0.0 and 1.0
+pub fn parse_jitter(value: &str) -> Result<f64, String> {
+    let jitter: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid jitter: {}", value))?;
+
+    if !(0.0..=1.0).contains(&jitter) {
+        return Err("jitter must be between 0.0 and 1.0".to_string());
+    }
+
+    Ok(jitter)
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::myrient::Rom;
+
+// longest file name most filesystems allow, in bytes on Linux and UTF-16 units on Windows
+const MAX_NAME_LENGTH: usize = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Profile {
+    /// Only / and NUL are replaced
+    Posix,
+    /// Names valid on NTFS and exFAT: no <>:"\|?* or control characters, no
+    /// trailing dots or spaces and no reserved names such as CON or NUL
+    WindowsSafe,
+    /// Like windows-safe, with names in ASCII for older devices reading FAT32 cards
+    Fat32,
+}
+
+const WINDOWS_RESERVED_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn name_length(name: &str, profile: Profile) -> usize {
+    match profile {
+        Profile::Posix => name.len(),
+        Profile::WindowsSafe | Profile::Fat32 => name.encode_utf16().count(),
+    }
+}
+
+// shortens the name before its extension
+fn truncate(name: String, profile: Profile) -> String {
+    if name_length(&name, profile) <= MAX_NAME_LENGTH {
+        return name;
+    }
+
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 && name.len() - dot <= 10 => name.split_at(dot),
+        _ => (name.as_str(), ""),
+    };
+
+    let mut stem = stem.to_string();
+    while !stem.is_empty()
+        && name_length(&stem, profile) + name_length(extension, profile) > MAX_NAME_LENGTH
+    {
+        stem.pop();
+    }
+
+    format!("{}{}", stem.trim_end(), extension)
+}
+
+/// Makes a single file or folder name valid for the profile. The result is
+/// never empty, "." or "..", so it cannot leave its parent folder.
+pub fn file_name(name: &str, profile: Profile) -> String {
+    let mut name: String = match profile {
+        Profile::Posix => name
+            .chars()
+            .map(|c| if c == '/' || c == '\0' { '_' } else { c })
+            .collect(),
+        Profile::WindowsSafe | Profile::Fat32 => {
+            let name = match profile {
+                Profile::Fat32 => deunicode::deunicode(name),
+                _ => name.to_string(),
+            };
+
+            let name: String = name
+                .chars()
+                .map(|c| {
+                    if c.is_control() || WINDOWS_RESERVED_CHARS.contains(&c) {
+                        '_'
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+
+            let mut name = name.trim_end_matches(['.', ' ']).to_string();
+
+            let stem = name.split('.').next().unwrap_or_default().trim_end();
+            if WINDOWS_RESERVED_NAMES
+                .iter()
+                .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+            {
+                name.insert(stem.len(), '_');
+            }
+
+            name
+        }
+    };
+
+    if name.is_empty() || name == "." || name == ".." {
+        name = "_".to_string();
+    }
+
+    truncate(name, profile)
+}
+
+/// Makes every folder and file name of a path relative to the output folder
+/// valid for the profile. Leading separators are dropped and ".." is replaced,
+/// so the result always stays inside the output folder. Backslashes only
+/// separate folders on the Windows profiles.
+pub fn path(path: &str, profile: Profile) -> String {
+    let separators: &[char] = match profile {
+        Profile::Posix => &['/'],
+        Profile::WindowsSafe | Profile::Fat32 => &['/', '\\'],
+    };
+    path.split(separators)
+        .filter(|part| !part.is_empty() && *part != ".")
+        .map(|part| file_name(part, profile))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Sets the path of each ROM to a valid one for the profile. Files renamed in
+/// an earlier run are looked up from `names`, which maps the unsanitized paths
+/// to the local ones, so they are resumed and skipped even if the rules have
+/// changed since. New renames are added to `names`.
+pub fn apply(
+    roms: &mut [Rom],
+    profile: Profile,
+    output_dir: &str,
+    names: &mut HashMap<String, String>,
+) {
+    for rom in roms.iter_mut() {
+        let raw = rom.local_path().to_string();
+
+        // saved paths are still kept inside the output folder
+        let local = match names.get(&raw).map(|local| path(local, Profile::Posix)) {
+            Some(local) if Path::new(output_dir).join(&local).exists() => local,
+            _ => path(&raw, profile),
+        };
+
+        if local != raw {
+            names.insert(raw, local.clone());
+        }
+        if local != rom.file {
+            rom.path = Some(local);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posix_only_replaces_slashes() {
+        assert_eq!(file_name("A/B: C?", Profile::Posix), "A_B: C?");
+        assert_eq!(file_name("CON.zip", Profile::Posix), "CON.zip");
+    }
+
+    #[test]
+    fn windows_replaces_reserved_characters() {
+        assert_eq!(
+            file_name("Game: \"Part\" <1>?", Profile::WindowsSafe),
+            "Game_ _Part_ _1__"
+        );
+        assert_eq!(file_name("Game. ", Profile::WindowsSafe), "Game");
+    }
+
+    #[test]
+    fn windows_renames_reserved_names() {
+        assert_eq!(file_name("CON", Profile::WindowsSafe), "CON_");
+        assert_eq!(file_name("nul.tar.gz", Profile::WindowsSafe), "nul_.tar.gz");
+        assert_eq!(
+            file_name("Console.zip", Profile::WindowsSafe),
+            "Console.zip"
+        );
+    }
+
+    #[test]
+    fn fat32_transliterates_to_ascii() {
+        assert_eq!(file_name("Pokémon.zip", Profile::Fat32), "Pokemon.zip");
+    }
+
+    #[test]
+    fn never_leaves_the_parent_folder() {
+        for profile in Profile::value_variants() {
+            assert_eq!(file_name("..", *profile), "_");
+            assert_eq!(file_name("", *profile), "_");
+        }
+        assert_eq!(file_name("...", Profile::WindowsSafe), "_");
+    }
+
+    #[test]
+    fn truncates_long_names_before_the_extension() {
+        let name = file_name(&format!("{}.zip", "a".repeat(300)), Profile::Posix);
+
+        assert_eq!(name.len(), MAX_NAME_LENGTH);
+        assert!(name.ends_with("a.zip"));
+    }
+
+    #[test]
+    fn truncates_by_utf16_units_on_windows() {
+        let name = file_name(&format!("{}.zip", "é".repeat(300)), Profile::WindowsSafe);
+
+        assert_eq!(name.encode_utf16().count(), MAX_NAME_LENGTH);
+    }
+
+    #[test]
+    fn paths_stay_inside_the_output_folder() {
+        assert_eq!(path("/a/../b/./c.zip", Profile::Posix), "a/_/b/c.zip");
+        assert_eq!(path("..\\..\\c.zip", Profile::WindowsSafe), "_/_/c.zip");
+        assert_eq!(path("a\\b.zip", Profile::Posix), "a\\b.zip");
+        assert_eq!(path("a:b/CON", Profile::WindowsSafe), "a_b/CON_");
+    }
+}
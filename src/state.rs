@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants;
+use crate::myrient::Rom;
+
+// ROMs that failed to download in the last run, along with where they came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedDownloads {
+    pub catalog_url: String,
+    pub collection_url: String,
+    pub roms: Vec<Rom>,
+}
+
+fn state_dir(output_dir: &str) -> PathBuf {
+    Path::new(output_dir).join(constants::STATE_DIR)
+}
+
+fn failed_roms_path(output_dir: &str) -> PathBuf {
+    state_dir(output_dir).join(constants::FAILED_ROMS_FILE)
+}
+
+pub fn save_failed_roms(output_dir: &str, failed: &FailedDownloads) -> io::Result<PathBuf> {
+    fs::create_dir_all(state_dir(output_dir))?;
+
+    let path = failed_roms_path(output_dir);
+    let json = serde_json::to_string_pretty(failed)?;
+    fs::write(&path, json)?;
+
+    Ok(path)
+}
+
+pub fn load_failed_roms(output_dir: &str) -> io::Result<FailedDownloads> {
+    let json = fs::read_to_string(failed_roms_path(output_dir))?;
+
+    Ok(serde_json::from_str(&json)?)
+}
+
+pub fn clear_failed_roms(output_dir: &str) -> io::Result<()> {
+    let path = failed_roms_path(output_dir);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+fn names_path(output_dir: &str) -> PathBuf {
+    state_dir(output_dir).join(constants::NAMES_FILE)
+}
+
+/// Local paths of files renamed for the filesystem, keyed by their server paths
+pub fn load_names(output_dir: &str) -> io::Result<HashMap<String, String>> {
+    let json = fs::read_to_string(names_path(output_dir))?;
+
+    Ok(serde_json::from_str(&json)?)
+}
+
+pub fn save_names(output_dir: &str, names: &HashMap<String, String>) -> io::Result<()> {
+    fs::create_dir_all(state_dir(output_dir))?;
+
+    let json = serde_json::to_string_pretty(names)?;
+    fs::write(names_path(output_dir), json)
+}
+
+// DAT-file the output folder was last completely downloaded from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Synced {
+    pub name: String,
+    pub version: Option<String>,
+    pub date: Option<String>,
+}
+
+/// Saves the DAT-file along with its header, so later runs can download only
+/// what changed since
+pub fn save_synced(output_dir: &str, synced: &Synced, dat_text: &str) -> io::Result<()> {
+    fs::create_dir_all(state_dir(output_dir))?;
+
+    fs::write(
+        state_dir(output_dir).join(constants::SYNCED_DAT_FILE),
+        dat_text,
+    )?;
+
+    let json = serde_json::to_string_pretty(synced)?;
+    fs::write(state_dir(output_dir).join(constants::SYNCED_FILE), json)
+}
+
+/// The header and text of the DAT-file the output was last synced to
+pub fn load_synced(output_dir: &str) -> io::Result<(Synced, String)> {
+    let json = fs::read_to_string(state_dir(output_dir).join(constants::SYNCED_FILE))?;
+    let dat_text = fs::read_to_string(state_dir(output_dir).join(constants::SYNCED_DAT_FILE))?;
+
+    Ok((serde_json::from_str(&json)?, dat_text))
+}
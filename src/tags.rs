@@ -0,0 +1,256 @@
+// Parsing of No-Intro/Redump naming conventions, e.g.
+// "Game Title (USA, Europe) (En,Fr,De) (Rev 1) (Beta)"
+
+const REGIONS: &[&str] = &[
+    "Argentina",
+    "Asia",
+    "Australia",
+    "Austria",
+    "Belgium",
+    "Brazil",
+    "Canada",
+    "China",
+    "Croatia",
+    "Czech",
+    "Denmark",
+    "Europe",
+    "Finland",
+    "France",
+    "Germany",
+    "Greece",
+    "Hong Kong",
+    "India",
+    "Ireland",
+    "Israel",
+    "Italy",
+    "Japan",
+    "Korea",
+    "Latin America",
+    "Mexico",
+    "Netherlands",
+    "New Zealand",
+    "Norway",
+    "Poland",
+    "Portugal",
+    "Russia",
+    "Scandinavia",
+    "Singapore",
+    "South Africa",
+    "Spain",
+    "Sweden",
+    "Switzerland",
+    "Taiwan",
+    "Turkey",
+    "UK",
+    "Unknown",
+    "USA",
+    "World",
+];
+
+// Languages implied by a region when the name has no language tag
+const REGION_LANGUAGES: &[(&str, &str)] = &[
+    ("Australia", "En"),
+    ("Brazil", "Pt"),
+    ("Canada", "En"),
+    ("China", "Zh"),
+    ("France", "Fr"),
+    ("Germany", "De"),
+    ("Italy", "It"),
+    ("Japan", "Ja"),
+    ("Korea", "Ko"),
+    ("Netherlands", "Nl"),
+    ("Russia", "Ru"),
+    ("Spain", "Es"),
+    ("Sweden", "Sv"),
+    ("UK", "En"),
+    ("USA", "En"),
+    ("World", "En"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct NameTags {
+    /// Name with all tags removed
+    pub title: String,
+    pub regions: Vec<String>,
+    /// Languages from the name, or implied by the regions if not listed
+    pub languages: Vec<String>,
+    /// Revision such as "1" or "A" from "(Rev 1)"
+    pub revision: Option<String>,
+    /// Version such as "1.1" from "(v1.1)"
+    pub version: Option<String>,
+    /// Disc number from "(Disc 1)"
+    pub disc: Option<u32>,
+    /// All other tags, e.g. "Beta", "Proto 2", "Virtual Console", "!"
+    pub flags: Vec<String>,
+}
+
+impl NameTags {
+    pub fn has_region(&self, region: &str) -> bool {
+        self.regions.iter().any(|r| r.eq_ignore_ascii_case(region))
+    }
+
+    pub fn has_language(&self, language: &str) -> bool {
+        self.languages
+            .iter()
+            .any(|l| l.eq_ignore_ascii_case(language))
+    }
+
+    /// Matches flags by their first words, so "beta" matches "Beta 2"
+    pub fn has_flag(&self, flag: &str) -> bool {
+        let flag = flag.to_lowercase();
+
+        self.flags.iter().any(|f| {
+            let f = f.to_lowercase();
+            f == flag || f.starts_with(&format!("{} ", flag))
+        })
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        ["beta", "proto", "demo", "sample", "preview", "kiosk"]
+            .iter()
+            .any(|flag| self.has_flag(flag))
+    }
+}
+
+fn is_region_tag(tag: &str) -> bool {
+    tag.split(", ").all(|part| REGIONS.contains(&part))
+}
+
+// "En", "Fr", "Pt-BR" or "Zh-Hant", separated by commas
+fn is_language_tag(tag: &str) -> bool {
+    tag.split(',').all(|part| {
+        let mut chars = part.split('-').next().unwrap_or("").chars();
+
+        matches!(
+            (chars.next(), chars.next(), chars.next()),
+            (Some(first), Some(second), None) if first.is_ascii_uppercase() && second.is_ascii_lowercase()
+        )
+    })
+}
+
+// splits "Title (Tag 1) [Tag 2]" into the title and its tags
+fn split_tags(name: &str) -> (String, Vec<String>) {
+    let mut title = String::new();
+    let mut tags: Vec<String> = Vec::new();
+    let mut depth = 0;
+    let mut tag = String::new();
+
+    for c in name.chars() {
+        match c {
+            '(' | '[' => {
+                if depth > 0 {
+                    tag.push(c);
+                }
+                depth += 1;
+            }
+            ')' | ']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    tags.push(tag.trim().to_string());
+                    tag.clear();
+                } else {
+                    tag.push(c);
+                }
+            }
+            _ if depth > 0 => tag.push(c),
+            _ => title.push(c),
+        }
+    }
+
+    (title.split_whitespace().collect::<Vec<_>>().join(" "), tags)
+}
+
+/// Name with the "(Disc N)" tag removed, shared by all discs of a game
+pub fn without_disc(name: &str) -> String {
+    let (_, tags) = split_tags(name);
+
+    match tags.iter().find(|tag| tag.starts_with("Disc ")) {
+        None => name.to_string(),
+        Some(disc) => name
+            .replace(&format!(" ({})", disc), "")
+            .replace(&format!("({})", disc), ""),
+    }
+}
+
+pub fn parse(name: &str) -> NameTags {
+    let (title, tags) = split_tags(name);
+    let mut parsed = NameTags {
+        title,
+        ..NameTags::default()
+    };
+
+    for tag in tags {
+        if parsed.regions.is_empty() && is_region_tag(&tag) {
+            parsed.regions = tag.split(", ").map(String::from).collect();
+        } else if parsed.languages.is_empty() && is_language_tag(&tag) {
+            parsed.languages = tag.split(',').map(String::from).collect();
+        } else if let Some(revision) = tag.strip_prefix("Rev ") {
+            parsed.revision = Some(revision.to_string());
+        } else if let Some(disc) = tag.strip_prefix("Disc ").and_then(|disc| disc.parse().ok()) {
+            parsed.disc = Some(disc);
+        } else if tag.starts_with('v') && tag[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            parsed.version = Some(tag[1..].to_string());
+        } else {
+            parsed.flags.push(tag);
+        }
+    }
+
+    if parsed.languages.is_empty() {
+        for region in parsed.regions.iter() {
+            if let Some((_, language)) = REGION_LANGUAGES.iter().find(|(r, _)| r == region) {
+                if !parsed.languages.iter().any(|l| l == language) {
+                    parsed.languages.push(language.to_string());
+                }
+            }
+        }
+    }
+
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_regions_languages_and_revision() {
+        let tags = parse("Game Title (USA, Europe) (En,Fr,De) (Rev 1) (Beta)");
+
+        assert_eq!(tags.title, "Game Title");
+        assert_eq!(tags.regions, ["USA", "Europe"]);
+        assert_eq!(tags.languages, ["En", "Fr", "De"]);
+        assert_eq!(tags.revision.as_deref(), Some("1"));
+        assert_eq!(tags.flags, ["Beta"]);
+        assert!(tags.is_prerelease());
+    }
+
+    #[test]
+    fn implies_languages_from_regions() {
+        let tags = parse("Game (Japan, USA)");
+
+        assert_eq!(tags.languages, ["Ja", "En"]);
+    }
+
+    #[test]
+    fn parses_versions_and_discs() {
+        let tags = parse("Game (Europe) (v1.02) (Disc 2) [b]");
+
+        assert_eq!(tags.version.as_deref(), Some("1.02"));
+        assert_eq!(tags.disc, Some(2));
+        assert_eq!(tags.flags, ["b"]);
+    }
+
+    #[test]
+    fn keeps_unknown_tags_as_flags() {
+        let tags = parse("Game (Demo) (Proto 2)");
+
+        assert!(tags.regions.is_empty());
+        assert_eq!(tags.flags, ["Demo", "Proto 2"]);
+    }
+
+    #[test]
+    fn removes_the_disc_tag() {
+        assert_eq!(without_disc("Game (USA) (Disc 1)"), "Game (USA)");
+        assert_eq!(without_disc("Game (USA)"), "Game (USA)");
+    }
+}
@@ -0,0 +1,256 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveTime, Timelike};
+
+use crate::interrupt;
+
+// How often a paused queue checks whether it may continue
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Rate limit in bytes per second, `None` meaning unlimited
+pub type Rate = Option<u64>;
+
+#[derive(Debug, Clone)]
+pub struct RateWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub rate: Rate,
+}
+
+impl RateWindow {
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            // window wraps around midnight
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket shared by all downloads, with an optional rate per time of day
+pub struct Throttle {
+    default_rate: Rate,
+    windows: Vec<RateWindow>,
+    bucket: Mutex<Bucket>,
+}
+
+impl Throttle {
+    pub fn new(default_rate: Rate, windows: Vec<RateWindow>) -> Throttle {
+        Throttle {
+            default_rate,
+            windows,
+            bucket: Mutex::new(Bucket {
+                tokens: 0.0,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn current_rate(&self) -> Rate {
+        let now = Local::now().time();
+
+        self.windows
+            .iter()
+            .find(|window| window.contains(now))
+            .map_or(self.default_rate, |window| window.rate)
+    }
+
+    /// Blocks while the current window pauses downloading. `on_pause` is
+    /// called once with the time downloading continues, if known.
+    pub fn wait_while_paused<F: FnOnce(Option<NaiveTime>)>(&self, on_pause: F) {
+        if self.current_rate() != Some(0) {
+            return;
+        }
+
+        let now = Local::now().time();
+        let resume_at = self
+            .windows
+            .iter()
+            .find(|window| window.contains(now))
+            .map(|window| window.end);
+        on_pause(resume_at);
+
+        while self.current_rate() == Some(0) && !interrupt::is_interrupted() {
+            interrupt::sleep(PAUSE_POLL_INTERVAL);
+        }
+    }
+
+    /// Takes `bytes` from the bucket, sleeping until enough tokens are available
+    pub fn consume(&self, bytes: usize) {
+        loop {
+            let rate = match self.current_rate() {
+                None => return,
+                Some(0) if interrupt::is_interrupted() => return,
+                Some(0) => {
+                    interrupt::sleep(PAUSE_POLL_INTERVAL);
+                    continue;
+                }
+                Some(rate) => rate as f64,
+            };
+
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+
+                // allow bursts of up to one second worth of data
+                bucket.tokens = (bucket.tokens + elapsed * rate).min(rate);
+                bucket.last_refill = now;
+                bucket.tokens -= bytes as f64;
+
+                if bucket.tokens < 0.0 {
+                    Duration::from_secs_f64(-bucket.tokens / rate)
+                } else {
+                    Duration::ZERO
+                }
+            };
+
+            thread::sleep(wait);
+            return;
+        }
+    }
+}
+
+// parses an amount of bytes per second, which may round down to 0
+fn parse_bytes(value: &str) -> Result<Rate, String> {
+    let value = value.trim();
+
+    if matches!(value.to_lowercase().as_str(), "unlimited" | "full") {
+        return Ok(None);
+    }
+
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1_000.0),
+        Some((i, 'm' | 'M')) => (&value[..i], 1_000_000.0),
+        Some((i, 'g' | 'G')) => (&value[..i], 1_000_000_000.0),
+        _ => (value, 1.0),
+    };
+
+    match number.trim().parse::<f64>() {
+        Ok(number) if number >= 0.0 => Ok(Some((number * multiplier) as u64)),
+        _ => Err(format!("invalid rate '{}'", value)),
+    }
+}
+
+/// Parses a rate such as `500K`, `2M` or `1.5G` into bytes per second, or
+/// `unlimited` for no limit. Pausing is only possible in schedule windows.
+pub fn parse_rate(value: &str) -> Result<Rate, String> {
+    if value.trim().eq_ignore_ascii_case("pause") {
+        return Err("'pause' can only be used in --schedule windows".to_string());
+    }
+
+    match parse_bytes(value)? {
+        Some(0) => Err(format!(
+            "rate '{}' is below 1 byte per second",
+            value.trim()
+        )),
+        rate => Ok(rate),
+    }
+}
+
+// rate of a schedule window, where `pause` or `0` stops downloading
+fn parse_window_rate(value: &str) -> Result<Rate, String> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("pause") || value == "0" {
+        return Ok(Some(0));
+    }
+
+    parse_rate(value)
+}
+
+/// Parses a window such as `01:00-07:00=unlimited` or `18:00-23:00=2M`
+pub fn parse_window(value: &str) -> Result<RateWindow, String> {
+    let (times, rate) = value
+        .split_once('=')
+        .ok_or_else(|| format!("missing '=<rate>' in window '{}'", value))?;
+    let (start, end) = times
+        .split_once('-')
+        .ok_or_else(|| format!("missing '-' between times in window '{}'", value))?;
+
+    let parse_time = |time: &str| {
+        NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map_err(|_| format!("invalid time '{}', expected HH:MM", time))
+    };
+
+    Ok(RateWindow {
+        start: parse_time(start)?,
+        end: parse_time(end)?,
+        rate: parse_window_rate(rate)?,
+    })
+}
+
+pub fn format_rate(rate: Rate) -> String {
+    match rate {
+        None => "unlimited".to_string(),
+        Some(0) => "paused".to_string(),
+        Some(rate) => format!("{:.2} MB/s", rate as f64 / 1_000_000.0),
+    }
+}
+
+pub fn format_time(time: NaiveTime) -> String {
+    format!("{:02}:{:02}", time.hour(), time.minute())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_rates_with_suffixes() {
+        assert_eq!(parse_rate("500K"), Ok(Some(500_000)));
+        assert_eq!(parse_rate("2m"), Ok(Some(2_000_000)));
+        assert_eq!(parse_rate("1.5G"), Ok(Some(1_500_000_000)));
+        assert_eq!(parse_rate(" 100 "), Ok(Some(100)));
+        assert_eq!(parse_rate("unlimited"), Ok(None));
+    }
+
+    #[test]
+    fn rejects_invalid_rates() {
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("-1M").is_err());
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("0.5").is_err());
+        assert!(parse_rate("pause").is_err());
+    }
+
+    #[test]
+    fn parses_windows() {
+        let window = parse_window("18:00-23:30=2M").unwrap();
+
+        assert_eq!(window.start, time(18, 0));
+        assert_eq!(window.end, time(23, 30));
+        assert_eq!(window.rate, Some(2_000_000));
+        assert_eq!(parse_window("01:00-07:00=pause").unwrap().rate, Some(0));
+        assert_eq!(parse_window("01:00-07:00=0").unwrap().rate, Some(0));
+    }
+
+    #[test]
+    fn rejects_invalid_windows() {
+        assert!(parse_window("18:00-23:00").is_err());
+        assert!(parse_window("18:00=2M").is_err());
+        assert!(parse_window("25:00-23:00=2M").is_err());
+        assert!(parse_window("18:00-23:00=0.5").is_err());
+    }
+
+    #[test]
+    fn windows_wrap_around_midnight() {
+        let window = parse_window("22:00-06:00=1M").unwrap();
+
+        assert!(window.contains(time(23, 0)));
+        assert!(window.contains(time(5, 59)));
+        assert!(!window.contains(time(6, 0)));
+        assert!(!window.contains(time(12, 0)));
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Stderr};
+
+use indicatif::DecimalBytes;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+
+use crate::myrient::{self, Catalog, Collection, Rom};
+
+const PAGE_SIZE: usize = 20;
+
+// What was chosen in the browser
+#[derive(Debug, Clone)]
+pub struct Selection {
+    pub catalog_url: String,
+    pub collection_url: String,
+    pub roms: Vec<Rom>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Catalogs,
+    Collections,
+    Roms,
+}
+
+// Matched and missing wanted ROMs in a collection
+#[derive(Debug, Clone, Copy)]
+struct Preview {
+    files: usize,
+    matched: usize,
+    missing: usize,
+}
+
+// Labels narrowed down by a case-insensitive filter
+struct FilteredList {
+    labels: Vec<String>,
+    filter: String,
+    /// Indexes of the labels matching the filter
+    visible: Vec<usize>,
+    state: ListState,
+}
+
+impl FilteredList {
+    fn new(labels: Vec<String>, filter: &str) -> FilteredList {
+        let mut list = FilteredList {
+            labels,
+            filter: filter.to_string(),
+            visible: Vec::new(),
+            state: ListState::default(),
+        };
+        list.refilter();
+        list
+    }
+
+    fn refilter(&mut self) {
+        let words: Vec<String> = self
+            .filter
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+
+        self.visible = (0..self.labels.len())
+            .filter(|&index| {
+                let label = self.labels[index].to_lowercase();
+                words.iter().all(|word| label.contains(word.as_str()))
+            })
+            .collect();
+
+        self.state.select((!self.visible.is_empty()).then_some(0));
+    }
+
+    fn edit_filter(&mut self, key: &KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.filter.clear()
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.filter.push(c)
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            _ => return false,
+        }
+
+        self.refilter();
+        true
+    }
+
+    fn navigate(&mut self, key: &KeyEvent) -> bool {
+        let last = match self.visible.len() {
+            0 => return false,
+            len => len - 1,
+        };
+        let current = self.state.selected().unwrap_or(0);
+
+        let next = match key.code {
+            KeyCode::Up => current.saturating_sub(1),
+            KeyCode::Down => (current + 1).min(last),
+            KeyCode::PageUp => current.saturating_sub(PAGE_SIZE),
+            KeyCode::PageDown => (current + PAGE_SIZE).min(last),
+            KeyCode::Home => 0,
+            KeyCode::End => last,
+            _ => return false,
+        };
+
+        self.state.select(Some(next));
+        true
+    }
+
+    /// Index of the highlighted label
+    fn selected(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|index| self.visible.get(index).copied())
+    }
+}
+
+struct App {
+    wanted: HashSet<String>,
+    screen: Screen,
+    status: String,
+
+    catalogs: Vec<Catalog>,
+    catalog_list: FilteredList,
+    catalog_url: String,
+    catalog_title: String,
+
+    collections: Vec<Collection>,
+    collection_list: FilteredList,
+    collection_filter: String,
+    previews: HashMap<String, Preview>,
+    collection_url: String,
+    collection_title: String,
+
+    roms: Vec<Rom>,
+    checked: Vec<bool>,
+    rom_list: FilteredList,
+}
+
+impl App {
+    fn preview(&self, roms: &HashMap<String, Rom>) -> Preview {
+        let matched = self
+            .wanted
+            .iter()
+            .filter(|name| roms.contains_key(*name))
+            .count();
+
+        Preview {
+            files: roms.len(),
+            matched,
+            missing: self.wanted.len() - matched,
+        }
+    }
+
+    fn fetch_roms(&mut self, collection_url: &str) -> Option<HashMap<String, Rom>> {
+        match myrient::fetch(&format!("{}{}", self.catalog_url, collection_url)) {
+            Ok(html) => {
+                let roms = myrient::get_roms_for_collection(&html);
+                self.previews
+                    .insert(collection_url.to_string(), self.preview(&roms));
+                Some(roms)
+            }
+            Err(e) => {
+                self.status = e.to_string();
+                None
+            }
+        }
+    }
+
+    fn open_catalog(&mut self, index: usize) {
+        let catalog_url = self.catalogs[index].url.clone();
+
+        match myrient::fetch(&catalog_url) {
+            Ok(html) => {
+                self.collections = myrient::get_collections(&html);
+                self.collection_list = FilteredList::new(
+                    self.collections.iter().map(|c| c.title.clone()).collect(),
+                    &self.collection_filter,
+                );
+                self.previews.clear();
+                self.catalog_url = catalog_url;
+                self.catalog_title = self.catalogs[index].title.clone();
+                self.screen = Screen::Collections;
+            }
+            Err(e) => self.status = e.to_string(),
+        }
+    }
+
+    fn open_collection(&mut self, index: usize) {
+        let collection_url = self.collections[index].url.clone();
+
+        let roms = match self.fetch_roms(&collection_url) {
+            Some(roms) => roms,
+            None => return,
+        };
+
+        self.roms = roms.into_values().collect();
+        self.roms.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // wanted ROMs are selected up front
+        self.checked = self
+            .roms
+            .iter()
+            .map(|rom| self.wanted.contains(&rom.name))
+            .collect();
+
+        self.rom_list = FilteredList::new(
+            self.roms
+                .iter()
+                .map(|rom| match rom.size {
+                    Some(size) => format!("{} ({})", rom.file, DecimalBytes(size)),
+                    None => rom.file.clone(),
+                })
+                .collect(),
+            "",
+        );
+        self.collection_url = collection_url;
+        self.collection_title = self.collections[index].title.clone();
+        self.screen = Screen::Roms;
+    }
+
+    fn toggle_visible(&mut self) {
+        let all_checked = self.rom_list.visible.iter().all(|&i| self.checked[i]);
+        for &index in self.rom_list.visible.iter() {
+            self.checked[index] = !all_checked;
+        }
+    }
+
+    // handles a key, returning whether the browser is done
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Option<Selection>> {
+        self.status.clear();
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('c') {
+            return Some(None);
+        }
+
+        match self.screen {
+            Screen::Catalogs => match key.code {
+                KeyCode::Esc => return Some(None),
+                KeyCode::Enter => {
+                    if let Some(index) = self.catalog_list.selected() {
+                        self.open_catalog(index);
+                    }
+                }
+                _ => {
+                    let _ = self.catalog_list.navigate(&key) || self.catalog_list.edit_filter(&key);
+                }
+            },
+            Screen::Collections => match key.code {
+                KeyCode::Esc => self.screen = Screen::Catalogs,
+                KeyCode::Tab => {
+                    if let Some(index) = self.collection_list.selected() {
+                        let collection_url = self.collections[index].url.clone();
+                        self.fetch_roms(&collection_url);
+                    }
+                }
+                KeyCode::Enter => {
+                    if let Some(index) = self.collection_list.selected() {
+                        self.open_collection(index);
+                    }
+                }
+                _ => {
+                    let _ = self.collection_list.navigate(&key)
+                        || self.collection_list.edit_filter(&key);
+                }
+            },
+            Screen::Roms => match key.code {
+                KeyCode::Esc => self.screen = Screen::Collections,
+                KeyCode::Tab => {
+                    if let Some(index) = self.rom_list.selected() {
+                        self.checked[index] = !self.checked[index];
+                        let _ = self.rom_list.navigate(&KeyEvent::from(KeyCode::Down));
+                    }
+                }
+                KeyCode::Char('a') if ctrl => self.toggle_visible(),
+                KeyCode::Enter => {
+                    let roms: Vec<Rom> = self
+                        .roms
+                        .iter()
+                        .zip(self.checked.iter())
+                        .filter(|(_, checked)| **checked)
+                        .map(|(rom, _)| rom.clone())
+                        .collect();
+
+                    if roms.is_empty() {
+                        self.status = "Nothing selected".to_string();
+                    } else {
+                        return Some(Some(Selection {
+                            catalog_url: self.catalog_url.clone(),
+                            collection_url: self.collection_url.clone(),
+                            roms,
+                        }));
+                    }
+                }
+                _ => {
+                    let _ = self.rom_list.navigate(&key) || self.rom_list.edit_filter(&key);
+                }
+            },
+        }
+
+        None
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [title_area, filter_area, main_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let (title, help) = match self.screen {
+            Screen::Catalogs => (
+                "Catalogs".to_string(),
+                "Type to filter  Enter open  Esc quit",
+            ),
+            Screen::Collections => (
+                format!("Collections in {}", self.catalog_title),
+                "Type to filter  Tab preview  Enter open  Esc back",
+            ),
+            Screen::Roms => (
+                format!(
+                    "Files in {} / {}, {} selected",
+                    self.catalog_title,
+                    self.collection_title,
+                    self.checked.iter().filter(|checked| **checked).count()
+                ),
+                "Type to filter  Tab toggle  Ctrl-A toggle shown  Enter download  Esc back",
+            ),
+        };
+
+        frame.render_widget(Paragraph::new(title).bold(), title_area);
+
+        let filter = match self.screen {
+            Screen::Catalogs => &self.catalog_list.filter,
+            Screen::Collections => &self.collection_list.filter,
+            Screen::Roms => &self.rom_list.filter,
+        };
+        frame.render_widget(Paragraph::new(format!("Filter: {}", filter)), filter_area);
+
+        let help_line = if self.status.is_empty() {
+            Line::from(help).dim()
+        } else {
+            Line::from(self.status.as_str()).red()
+        };
+        frame.render_widget(Paragraph::new(help_line), help_area);
+
+        match self.screen {
+            Screen::Catalogs => draw_list(frame, main_area, &mut self.catalog_list, None),
+            Screen::Collections => {
+                let [list_area, preview_area] =
+                    Layout::horizontal([Constraint::Min(20), Constraint::Length(30)])
+                        .areas(main_area);
+
+                draw_list(frame, list_area, &mut self.collection_list, None);
+                self.draw_preview(frame, preview_area);
+            }
+            Screen::Roms => draw_list(frame, main_area, &mut self.rom_list, Some(&self.checked)),
+        }
+    }
+
+    fn draw_preview(&self, frame: &mut Frame, area: Rect) {
+        let preview = self
+            .collection_list
+            .selected()
+            .and_then(|index| self.previews.get(&self.collections[index].url));
+
+        let lines: Vec<Line> = match preview {
+            None => vec![Line::from("Press Tab to load").dim()],
+            Some(preview) if self.wanted.is_empty() => {
+                vec![Line::from(format!("Files:   {}", preview.files))]
+            }
+            Some(preview) => vec![
+                Line::from(format!("Files:   {}", preview.files)),
+                Line::from(format!("Matched: {}", preview.matched)).green(),
+                Line::from(format!("Missing: {}", preview.missing)).yellow(),
+            ],
+        };
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title("Preview")),
+            area,
+        );
+    }
+}
+
+fn draw_list(frame: &mut Frame, area: Rect, list: &mut FilteredList, checked: Option<&[bool]>) {
+    let items: Vec<ListItem> = list
+        .visible
+        .iter()
+        .map(|&index| match checked {
+            Some(checked) if checked[index] => ListItem::new(format!("[x] {}", list.labels[index])),
+            Some(_) => ListItem::new(format!("[ ] {}", list.labels[index])),
+            None => ListItem::new(list.labels[index].as_str()),
+        })
+        .collect();
+
+    let title = format!("{}/{}", list.visible.len(), list.labels.len());
+    let widget = List::new(items)
+        .block(Block::bordered().title(title))
+        .highlight_style(Style::new().reversed());
+
+    frame.render_stateful_widget(widget, area, &mut list.state);
+}
+
+// restores the terminal even if browsing fails
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stderr(), LeaveAlternateScreen);
+    }
+}
+
+/// Browses catalogs and collections, previewing how many of the `wanted` ROMs
+/// each collection has, and lets the user pick the files to download. The
+/// filters start out with `catalog_filter` and `collection_filter`. Returns
+/// `None` if the user quits.
+pub fn browse(
+    wanted: &[String],
+    catalog_filter: &str,
+    collection_filter: &str,
+) -> Result<Option<Selection>, String> {
+    let catalogs = myrient::get_catalogs(&myrient::fetch("").map_err(|e| e.to_string())?);
+
+    let mut app = App {
+        wanted: wanted.iter().cloned().collect(),
+        screen: Screen::Catalogs,
+        status: String::new(),
+        catalog_list: FilteredList::new(
+            catalogs.iter().map(|c| c.title.clone()).collect(),
+            catalog_filter,
+        ),
+        catalogs,
+        catalog_url: String::new(),
+        catalog_title: String::new(),
+        collections: Vec::new(),
+        collection_list: FilteredList::new(Vec::new(), ""),
+        collection_filter: collection_filter.to_string(),
+        previews: HashMap::new(),
+        collection_url: String::new(),
+        collection_title: String::new(),
+        roms: Vec::new(),
+        checked: Vec::new(),
+        rom_list: FilteredList::new(Vec::new(), ""),
+    };
+
+    enable_raw_mode().map_err(|e| e.to_string())?;
+    let _guard = TerminalGuard;
+    execute!(io::stderr(), EnterAlternateScreen).map_err(|e| e.to_string())?;
+
+    let mut terminal: Terminal<CrosstermBackend<Stderr>> =
+        Terminal::new(CrosstermBackend::new(io::stderr())).map_err(|e| e.to_string())?;
+
+    loop {
+        terminal
+            .draw(|frame| app.draw(frame))
+            .map_err(|e| e.to_string())?;
+
+        if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(result) = app.handle_key(key) {
+                return Ok(result);
+            }
+        }
+    }
+}
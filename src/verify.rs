@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use md5::{Digest, Md5};
+use sha1::Sha1;
+use zip::ZipArchive;
+
+use crate::constants;
+use crate::dat::{self, DatRom, Game};
+use crate::sanitize;
+
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hashes {
+    pub size: u64,
+    /// Hashes in lowercase hex
+    pub crc: String,
+    pub md5: String,
+    pub sha1: String,
+}
+
+// One file expected by a DAT, checked in a local folder
+#[derive(Debug, Clone)]
+pub struct FileCheck {
+    /// File name without extension, as matched against the server
+    pub name: String,
+    /// Local file, if there is one
+    pub path: Option<PathBuf>,
+    pub verification: Verification,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// Matches the DAT
+    Verified,
+    /// Does not match the DAT, with the reason
+    Failed(String),
+    /// Nothing in the DAT to verify against
+    Unknown,
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verification::Verified => write!(f, "verified"),
+            Verification::Failed(reason) => write!(f, "failed: {}", reason),
+            Verification::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+pub fn hash_reader<R: Read>(mut reader: R) -> io::Result<Hashes> {
+    let mut crc = crc32fast::Hasher::new();
+    let mut md5 = Md5::new();
+    let mut sha1 = Sha1::new();
+    let mut size: u64 = 0;
+
+    let mut buf = vec![0; HASH_BUFFER_SIZE];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        crc.update(&buf[..n]);
+        md5.update(&buf[..n]);
+        sha1.update(&buf[..n]);
+        size += n as u64;
+    }
+
+    Ok(Hashes {
+        size,
+        crc: format!("{:08x}", crc.finalize()),
+        md5: format!("{:x}", md5.finalize()),
+        sha1: format!("{:x}", sha1.finalize()),
+    })
+}
+
+pub fn hash_file(path: &Path) -> io::Result<Hashes> {
+    hash_reader(File::open(path)?)
+}
+
+/// Whether the hashes match the DAT entry, ignoring hashes missing from the DAT
+pub fn matches_rom(hashes: &Hashes, rom: &DatRom) -> bool {
+    rom.size.is_none_or(|size| size == hashes.size)
+        && rom.crc.as_ref().is_none_or(|crc| *crc == hashes.crc)
+        && rom.md5.as_ref().is_none_or(|md5| *md5 == hashes.md5)
+        && rom.sha1.as_ref().is_none_or(|sha1| *sha1 == hashes.sha1)
+}
+
+pub fn is_zip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+// checks the sizes and CRCs stored in the zip against the DAT, without extracting
+fn verify_zip(path: &Path, roms: &[DatRom]) -> Verification {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => return Verification::Failed(e.to_string()),
+    };
+    let mut archive = match ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(e) => return Verification::Failed(format!("invalid zip: {}", e)),
+    };
+
+    for rom in roms {
+        let index = match archive.index_for_name(&rom.name) {
+            Some(index) => index,
+            None => return Verification::Failed(format!("{} missing from zip", rom.name)),
+        };
+        let entry = match archive.by_index_raw(index) {
+            Ok(entry) => entry,
+            Err(e) => return Verification::Failed(format!("invalid zip: {}", e)),
+        };
+
+        if rom.size.is_some_and(|size| size != entry.size()) {
+            return Verification::Failed(format!("{} has wrong size", rom.name));
+        }
+        if rom
+            .crc
+            .as_ref()
+            .is_some_and(|crc| *crc != format!("{:08x}", entry.crc32()))
+        {
+            return Verification::Failed(format!("{} has wrong CRC", rom.name));
+        }
+    }
+
+    Verification::Verified
+}
+
+/// Verifies a downloaded file against the DAT entries it should contain.
+/// Zips are checked by their contents, other files by their own hashes.
+/// `file_name` is the name of the file on the server, which differs from the
+/// local one for files renamed for the filesystem.
+pub fn verify_file(path: &Path, file_name: &str, roms: &[DatRom]) -> Verification {
+    if roms.is_empty() {
+        return Verification::Unknown;
+    }
+
+    if is_zip(path) {
+        return verify_zip(path, roms);
+    }
+
+    let rom = match roms.iter().find(|rom| rom.name == file_name) {
+        Some(rom) => rom,
+        None => return Verification::Unknown,
+    };
+
+    match hash_file(path) {
+        Ok(hashes) if matches_rom(&hashes, rom) => Verification::Verified,
+        Ok(_) => Verification::Failed("hash mismatch".to_string()),
+        Err(e) => Verification::Failed(e.to_string()),
+    }
+}
+
+/// All files in `dir` and its subfolders, except the run state
+pub fn list_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            if path
+                .file_name()
+                .is_some_and(|name| name != constants::STATE_DIR)
+            {
+                files.extend(list_files(&path)?);
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Local files by their name without extension, along with their name on the
+/// server. Files renamed for the filesystem are found by their server names
+/// from `names`.
+pub fn find_local_files(
+    dir: &Path,
+    names: &HashMap<String, String>,
+) -> io::Result<HashMap<String, (String, PathBuf)>> {
+    let mut local_files: HashMap<String, (String, PathBuf)> = HashMap::new();
+
+    for path in list_files(dir)? {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        local_files.insert(dat::file_stem(&name), (name, path));
+    }
+
+    for (server_path, local_path) in names.iter() {
+        let path = dir.join(sanitize::path(local_path, sanitize::Profile::Posix));
+        if path.is_file() {
+            let name = server_path.rsplit('/').next().unwrap_or_default();
+            local_files.insert(dat::file_stem(name), (name.to_string(), path));
+        }
+    }
+
+    Ok(local_files)
+}
+
+/// Checks every file the DAT expects in `dir`, including subfolders made by
+/// `--layout`. Local files are matched by their name without extension, like
+/// downloads are matched on the server, or by their server names from `names`
+/// if renamed.
+pub fn verify_folder(
+    dir: &Path,
+    games: &[Game],
+    names: &HashMap<String, String>,
+) -> io::Result<Vec<FileCheck>> {
+    let local_files = find_local_files(dir, names)?;
+
+    let roms_by_stem = dat::get_roms_by_stem(games);
+
+    let checks = dat::get_wanted_roms(games)
+        .into_iter()
+        .map(|name| {
+            let local_file = local_files.get(&name);
+            let verification = match local_file {
+                None => Verification::Failed("not found".to_string()),
+                Some((file_name, path)) => verify_file(
+                    path,
+                    file_name,
+                    roms_by_stem.get(&name).map_or(&[], |roms| roms.as_slice()),
+                ),
+            };
+
+            FileCheck {
+                name,
+                path: local_file.map(|(_, path)| path.clone()),
+                verification,
+            }
+        })
+        .collect();
+
+    Ok(checks)
+}
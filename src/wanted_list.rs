@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::myrient::Rom;
+use crate::tags;
+
+// Matches scoring lower than this are not considered the same title
+const MIN_MATCH_SCORE: f64 = 0.8;
+
+const LIST_EXTENSIONS: &[&str] = &["txt", "csv"];
+
+// Header names skipped on the first line of a CSV
+const CSV_HEADERS: &[&str] = &["name", "title", "game"];
+
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub query: String,
+    pub rom: Option<Rom>,
+    /// Similarity between the query and the matched title, 0.0 - 1.0
+    pub score: f64,
+}
+
+/// Whether the input is a plain list of titles rather than a DAT
+pub fn is_wanted_list(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| LIST_EXTENSIONS.contains(&extension.as_str()))
+}
+
+// first field of a CSV line, unquoting it if needed
+fn first_csv_field(line: &str) -> String {
+    let line = line.trim();
+
+    match line.strip_prefix('"') {
+        Some(quoted) => {
+            let mut field = String::new();
+            let mut chars = quoted.chars().peekable();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        field.push('"');
+                        chars.next();
+                    }
+                    '"' => break,
+                    _ => field.push(c),
+                }
+            }
+
+            field
+        }
+        None => line.split(',').next().unwrap_or_default().to_string(),
+    }
+}
+
+/// Reads titles from a newline separated text file, or the first column of a
+/// CSV. Empty lines and lines starting with `#` are skipped.
+pub fn read(path: &str) -> io::Result<Vec<String>> {
+    let text = fs::read_to_string(path)?;
+    let is_csv = path.to_lowercase().ends_with(".csv");
+
+    let mut titles: Vec<String> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let title = if is_csv {
+            first_csv_field(line)
+        } else {
+            line.to_string()
+        };
+        let title = title.trim();
+
+        if title.is_empty() || title.starts_with('#') {
+            continue;
+        }
+
+        if is_csv && index == 0 && CSV_HEADERS.contains(&title.to_lowercase().as_str()) {
+            continue;
+        }
+
+        titles.push(title.to_string());
+    }
+
+    Ok(titles)
+}
+
+// lowercase title without tags or punctuation
+fn normalize(name: &str) -> String {
+    tags::parse(name)
+        .title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn region_rank(name: &str, query_regions: &[String], region_priority: &[String]) -> usize {
+    let tags = tags::parse(name);
+
+    // regions given in the query itself come first
+    if query_regions.iter().any(|region| tags.has_region(region)) {
+        return 0;
+    }
+
+    tags.regions
+        .iter()
+        .filter_map(|region| {
+            region_priority
+                .iter()
+                .position(|priority| priority.eq_ignore_ascii_case(region))
+        })
+        .min()
+        .map_or(region_priority.len() + 1, |rank| rank + 1)
+}
+
+/// Finds the closest title in the collection for each query. Equally close
+/// titles are told apart by the regions in the query, then `region_priority`.
+pub fn match_titles(
+    queries: &[String],
+    available_roms: &HashMap<String, Rom>,
+    region_priority: &[String],
+) -> Vec<Match> {
+    let normalized: Vec<(String, &Rom)> = available_roms
+        .values()
+        .map(|rom| (normalize(&rom.name), rom))
+        .collect();
+
+    let mut matches: Vec<Match> = Vec::new();
+
+    for query in queries {
+        let exact = available_roms
+            .values()
+            .find(|rom| rom.name.eq_ignore_ascii_case(query));
+
+        if let Some(rom) = exact {
+            matches.push(Match {
+                query: query.clone(),
+                rom: Some(rom.clone()),
+                score: 1.0,
+            });
+            continue;
+        }
+
+        let normalized_query = normalize(query);
+        let query_regions = tags::parse(query).regions;
+        let rank = |rom: &Rom| region_rank(&rom.name, &query_regions, region_priority);
+
+        let mut candidates: Vec<(f64, &Rom)> = normalized
+            .iter()
+            .map(|(title, rom)| {
+                (
+                    strsim::normalized_levenshtein(&normalized_query, title),
+                    *rom,
+                )
+            })
+            .filter(|(score, _)| *score >= MIN_MATCH_SCORE)
+            .collect();
+
+        candidates.sort_by(|(score_a, rom_a), (score_b, rom_b)| {
+            score_b
+                .total_cmp(score_a)
+                .then_with(|| rank(rom_a).cmp(&rank(rom_b)))
+                .then_with(|| rom_a.name.len().cmp(&rom_b.name.len()))
+                .then_with(|| rom_a.name.cmp(&rom_b.name))
+        });
+        let best = candidates.first();
+
+        matches.push(match best {
+            Some((score, rom)) => Match {
+                query: query.clone(),
+                rom: Some((*rom).clone()),
+                score: *score,
+            },
+            None => Match {
+                query: query.clone(),
+                rom: None,
+                score: 0.0,
+            },
+        });
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_text(name: &str, text: &str) -> Vec<String> {
+        let path =
+            std::env::temp_dir().join(format!("auto-myrient-{}-{}", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        let titles = read(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        titles
+    }
+
+    fn roms(names: &[&str]) -> HashMap<String, Rom> {
+        names
+            .iter()
+            .map(|name| {
+                let rom = Rom {
+                    name: name.to_string(),
+                    file: format!("{}.zip", name),
+                    url: format!("{}.zip", name),
+                    size: None,
+                    path: None,
+                };
+                (name.to_string(), rom)
+            })
+            .collect()
+    }
+
+    fn matched(matches: &[Match]) -> Vec<Option<&str>> {
+        matches
+            .iter()
+            .map(|m| m.rom.as_ref().map(|rom| rom.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn detects_lists_by_extension() {
+        assert!(is_wanted_list("wanted.txt"));
+        assert!(is_wanted_list("Wanted.CSV"));
+        assert!(!is_wanted_list("system.dat"));
+    }
+
+    #[test]
+    fn reads_text_lists() {
+        let titles = read_text("list.txt", "Game A\n\n# comment\n  Game B  \n");
+
+        assert_eq!(titles, ["Game A", "Game B"]);
+    }
+
+    #[test]
+    fn reads_the_first_csv_column() {
+        let titles = read_text(
+            "list.csv",
+            "Name,Rating\nGame A,5\n\"Game, \"\"B\"\"\",4\n#skipped,1\n",
+        );
+
+        assert_eq!(titles, ["Game A", "Game, \"B\""]);
+    }
+
+    #[test]
+    fn matches_exact_names_ignoring_case() {
+        let available = roms(&["Game (USA)", "Game (Europe)"]);
+        let matches = match_titles(&["game (europe)".to_string()], &available, &[]);
+
+        assert_eq!(matched(&matches), [Some("Game (Europe)")]);
+        assert_eq!(matches[0].score, 1.0);
+    }
+
+    #[test]
+    fn prefers_regions_for_close_titles() {
+        let available = roms(&[
+            "Super Game (Japan)",
+            "Super Game (USA)",
+            "Super Game (Europe)",
+        ]);
+        let priority = vec!["Europe".to_string(), "USA".to_string()];
+
+        let matches = match_titles(
+            &["Super Game".to_string(), "Super Game (USA)".to_string()],
+            &available,
+            &priority,
+        );
+
+        assert_eq!(
+            matched(&matches),
+            [Some("Super Game (Europe)"), Some("Super Game (USA)")]
+        );
+    }
+
+    #[test]
+    fn matches_titles_with_small_differences() {
+        let available = roms(&["The Legend of Game - Part II (USA)"]);
+        let matches = match_titles(
+            &[
+                "the legend of game: part ii".to_string(),
+                "Another Game".to_string(),
+            ],
+            &available,
+            &[],
+        );
+
+        assert_eq!(
+            matched(&matches),
+            [Some("The Legend of Game - Part II (USA)"), None]
+        );
+        assert_eq!(matches[1].score, 0.0);
+    }
+}